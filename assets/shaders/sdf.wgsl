@@ -0,0 +1,112 @@
+// text from SDF fonts, the atlas alpha is 0.5 on the outline and moves 0.5 per spread pixels
+// towards 1.0 inside and 0.0 outside
+
+struct InstanceInput {
+    @location(5) model_matrix_0: vec4<f32>,
+    @location(6) model_matrix_1: vec4<f32>,
+    @location(7) model_matrix_2: vec4<f32>,
+    @location(8) model_matrix_3: vec4<f32>,
+};
+
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+};
+
+@group(1) @binding(0) var<uniform> camera: CameraUniform;
+
+// MaterialUniforms, the tint followed by SdfStyle::to_uniforms' custom_params
+struct SdfParams {
+    tint: vec4<f32>,
+    outline_color: vec4<f32>,
+    glow_color: vec4<f32>,
+    shadow_color: vec4<f32>,
+    shadow_offset: vec2<f32>,
+    spread: f32,
+    weight: f32,
+    outline_width: f32,
+    glow_width: f32,
+    shadow_softness: f32,
+    _padding: f32,
+};
+
+@group(2) @binding(0) var<uniform> params: SdfParams;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+};
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    let model_matrix = mat4x4<f32>(
+        instance.model_matrix_0,
+        instance.model_matrix_1,
+        instance.model_matrix_2,
+        instance.model_matrix_3,
+    );
+
+    var out: VertexOutput;
+    out.tex_coords = model.tex_coords;
+    out.clip_position = camera.view_proj * model_matrix * vec4<f32>(model.position, 1.0);
+    return out;
+}
+
+@group(0) @binding(0) var t_diffuse: texture_2d<f32>;
+@group(0) @binding(1) var s_diffuse: sampler;
+
+// premultiplied src drawn over premultiplied dst
+fn over(src: vec4<f32>, dst: vec4<f32>) -> vec4<f32> {
+    return src + dst * (1.0 - src.a);
+}
+
+fn layer(color: vec4<f32>, coverage: f32) -> vec4<f32> {
+    let a = color.a * coverage;
+    return vec4<f32>(color.rgb * a, a);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(t_diffuse));
+    let distance = textureSample(t_diffuse, s_diffuse, in.tex_coords).a;
+    let shadow_distance = textureSample(
+        t_diffuse,
+        s_diffuse,
+        in.tex_coords - params.shadow_offset * texel,
+    ).a;
+
+    // one glyph pixel in distance units, and about one screen pixel for antialiasing at any scale
+    let unit = 0.5 / params.spread;
+    let aa = max(fwidth(distance) * 0.5, 0.0001);
+
+    let edge = 0.5 - params.weight * unit;
+    let outline_edge = edge - params.outline_width * unit;
+    let glow_edge = outline_edge - params.glow_width * unit;
+
+    let fill = smoothstep(edge - aa, edge + aa, distance);
+    let outline = smoothstep(outline_edge - aa, outline_edge + aa, distance);
+    let glow = smoothstep(glow_edge - aa, outline_edge, distance) * step(0.0001, params.glow_width);
+    let shadow = smoothstep(
+        edge - params.shadow_softness * unit - aa,
+        edge + aa,
+        shadow_distance,
+    );
+
+    var color = layer(params.shadow_color, shadow);
+    color = over(layer(params.glow_color, glow), color);
+    color = over(layer(params.outline_color, outline), color);
+    color = over(layer(params.tint, fill), color);
+
+    // the pipeline blends straight alpha like the sprite shader
+    if color.a > 0.0 {
+        return vec4<f32>(color.rgb / color.a, color.a);
+    }
+    return vec4<f32>(0.0);
+}
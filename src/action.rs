@@ -0,0 +1,320 @@
+use crate::*;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use sdl3::keyboard::{Keycode, Scancode};
+use slotmap::SlotMap;
+
+// named actions, each triggered by any of its keybinds, e.g. "save" by Ctrl+S or a gamepad button
+pub struct ActionMap {
+    pub keybinds: SlotMap<KeybindKey, Keybind>,
+    pub actions: HashMap<String, Vec<KeybindKey>>,
+    pub sequence_timeout: Duration, // between the steps of a sequence keybind
+    pub capture_scancodes: bool,    // capture binds physical keys instead of layout keys
+    pending: Vec<Vec<HashSet<Button>>>,
+    pending_since: Option<u64>, // SDL event timestamp of the last step
+    capturing: Option<String>,
+    captured: Option<String>,
+}
+impl ActionMap {
+    pub fn new() -> Self {
+        Self {
+            keybinds: SlotMap::with_key(),
+            actions: HashMap::new(),
+            sequence_timeout: Duration::from_secs(2),
+            capture_scancodes: false,
+            pending: vec![],
+            pending_since: None,
+            capturing: None,
+            captured: None,
+        }
+    }
+
+    pub fn bind(&mut self, action: impl Into<String>, keybind: Keybind) -> KeybindKey {
+        let key = self.keybinds.insert(keybind);
+        self.actions.entry(action.into()).or_default().push(key);
+        key
+    }
+
+    pub fn unbind(&mut self, key: KeybindKey) {
+        self.keybinds.remove(key);
+        for keys in self.actions.values_mut() {
+            keys.retain(|k| *k != key);
+        }
+        self.actions.retain(|_, keys| !keys.is_empty());
+    }
+
+    pub fn remove_action(&mut self, action: &str) {
+        for key in self.actions.remove(action).unwrap_or_default() {
+            self.keybinds.remove(key);
+        }
+    }
+
+    // replaces the action's keybinds, the first new one takes over the callbacks and screen
+    // of the old ones so rebinding from a keymap keeps the action working
+    pub fn rebind(&mut self, action: &str, mut keybinds: Vec<Keybind>) {
+        let old = self.actions.remove(action).unwrap_or_default();
+        let mut old = old.into_iter().filter_map(|key| self.keybinds.remove(key));
+        if let Some(previous) = old.next() {
+            for keybind in &mut keybinds {
+                keybind.attatched_screen = keybind.attatched_screen.or(previous.attatched_screen);
+            }
+            if let Some(first) = keybinds.first_mut() {
+                first.callbacks = previous.callbacks;
+            }
+        }
+
+        for keybind in keybinds {
+            self.bind(action, keybind);
+        }
+    }
+
+    // the next chord pressed (held modifiers plus one other button) becomes the action's only
+    // keybind, Escape on its own cancels, nothing fires in the meantime
+    pub fn capture(&mut self, action: impl Into<String>) {
+        self.cancel_pending();
+        self.capturing = Some(action.into());
+    }
+
+    pub fn cancel_capture(&mut self) {
+        self.capturing = None;
+    }
+
+    pub fn capturing(&self) -> Option<&str> {
+        self.capturing.as_deref()
+    }
+
+    // the action that was just rebound by a capture, once
+    pub fn take_captured(&mut self) -> Option<String> {
+        self.captured.take()
+    }
+
+    fn update_capture(&mut self, button_states: &HashMap<Button, ButtonState>) {
+        // every key press shows up as both, only keep the kind being captured
+        let wanted_kind = |button: &Button| match button {
+            Button::Key(_) => !self.capture_scancodes,
+            Button::Scancode(_) => self.capture_scancodes,
+            _ => true,
+        };
+        let Some(pressed) = button_states
+            .iter()
+            .find(|(button, state)| {
+                state.is_pressed() && !button.is_modifier() && wanted_kind(button)
+            })
+            .map(|(button, _)| *button)
+        else {
+            return;
+        };
+        let Some(action) = self.capturing.take() else {
+            return;
+        };
+
+        let mut groups: Vec<Vec<Button>> = MODIFIERS
+            .iter()
+            .filter(|(_, keys)| {
+                keys.iter().any(|key| {
+                    button_states
+                        .get(&Button::Key(*key))
+                        .is_some_and(|state| state.is_down)
+                })
+            })
+            .map(|(_, keys)| keys.iter().map(|key| Button::Key(*key)).collect())
+            .collect();
+        let escape = matches!(
+            pressed,
+            Button::Key(Keycode::Escape) | Button::Scancode(Scancode::Escape)
+        );
+        if groups.is_empty() && escape {
+            return;
+        }
+
+        groups.push(vec![pressed]);
+        let keybind = Keybind::new(groups).with_modifier_exclusives();
+        self.rebind(&action, vec![keybind]);
+        self.captured = Some(action);
+    }
+
+    pub fn keybinds(&self, action: &str) -> impl Iterator<Item = &Keybind> {
+        self.actions
+            .get(action)
+            .into_iter()
+            .flatten()
+            .filter_map(|key| self.keybinds.get(*key))
+    }
+
+    // pressed this tick
+    pub fn is_triggered(&self, action: &str) -> bool {
+        self.keybinds(action)
+            .any(|keybind| keybind.state.is_pressed())
+    }
+
+    pub fn is_active(&self, action: &str) -> bool {
+        self.keybinds(action).any(|keybind| keybind.state.is_down)
+    }
+
+    // released this tick, and not still held through another keybind
+    pub fn is_released(&self, action: &str) -> bool {
+        self.keybinds(action)
+            .any(|keybind| keybind.state.is_released())
+            && !self.is_active(action)
+    }
+
+    // how many ticks the action has been held for
+    pub fn held_ticks(&self, action: &str) -> Option<u32> {
+        self.keybinds(action)
+            .filter(|keybind| keybind.state.is_down)
+            .map(|keybind| keybind.state.ticks)
+            .max()
+    }
+
+    pub fn held_for(&self, action: &str) -> Option<Duration> {
+        let now = sdl_now();
+        self.keybinds(action)
+            .filter_map(|keybind| keybind.state.held_for(now))
+            .max()
+    }
+
+    // the steps of a sequence entered so far, e.g. to show "Ctrl+K was pressed, waiting..."
+    pub fn pending(&self) -> &[Vec<HashSet<Button>>] {
+        &self.pending
+    }
+
+    // actions whose sequence can still be completed from the pending steps
+    pub fn pending_actions(&self) -> Vec<&str> {
+        if self.pending.is_empty() {
+            return vec![];
+        }
+        self.actions
+            .iter()
+            .filter(|(_, keys)| {
+                keys.iter()
+                    .filter_map(|key| self.keybinds.get(*key))
+                    .any(|keybind| keybind.prefix.starts_with(&self.pending))
+            })
+            .map(|(action, _)| action.as_str())
+            .collect()
+    }
+
+    pub fn cancel_pending(&mut self) {
+        self.pending.clear();
+        self.pending_since = None;
+    }
+
+    // pairs of actions where one keybind's steps start with another's on the same screen,
+    // the longer sequence always wins so the shorter one can never fire
+    pub fn conflicts(&self) -> Vec<(&str, &str)> {
+        let named: Vec<(&str, &Keybind)> = self
+            .actions
+            .iter()
+            .flat_map(|(action, keys)| {
+                keys.iter()
+                    .filter_map(|key| self.keybinds.get(*key))
+                    .map(move |keybind| (action.as_str(), keybind))
+            })
+            .collect();
+
+        let mut conflicts = vec![];
+        for (i, (action_a, a)) in named.iter().enumerate() {
+            for (action_b, b) in &named[i + 1..] {
+                let same_screen = a.attatched_screen.is_none()
+                    || b.attatched_screen.is_none()
+                    || a.attatched_screen == b.attatched_screen;
+                let shared_prefix = a.steps().zip(b.steps()).all(|(x, y)| x == y);
+                if same_screen && shared_prefix {
+                    conflicts.push((*action_a, *action_b));
+                }
+            }
+        }
+        conflicts
+    }
+
+    pub fn update(
+        &mut self,
+        button_states: &HashMap<Button, ButtonState>,
+        screens: &ScreenManager,
+    ) {
+        let now = sdl_now();
+        if self.capturing.is_some() {
+            self.update_capture(button_states);
+            for (_, keybind) in &mut self.keybinds {
+                keybind.update(false, now);
+            }
+            return;
+        }
+
+        if self.pending_since.is_some_and(|since| {
+            Duration::from_nanos(now.saturating_sub(since)) > self.sequence_timeout
+        }) {
+            self.cancel_pending();
+        }
+
+        // chords that start a sequence, pressing one waits for the rest instead of firing
+        let sequence_starts: Vec<Vec<HashSet<Button>>> = self
+            .keybinds
+            .values()
+            .filter(|keybind| keybind.is_on_screen(screens))
+            .filter_map(|keybind| keybind.prefix.first().cloned())
+            .collect();
+
+        let mut advanced = None;
+        let mut completed = false;
+        let mut expected = HashSet::new(); // buttons that continue a pending sequence
+        for (_, keybind) in &mut self.keybinds {
+            let held = keybind.state.is_down && keybind.is_satisfied(button_states, screens);
+            let step = self.pending.len();
+
+            let active = if !keybind.is_on_screen(screens) {
+                false
+            } else if held {
+                // keep firing held callbacks even once the sequence is no longer pending
+                true
+            } else if keybind.prefix.is_empty() {
+                self.pending.is_empty()
+                    && !sequence_starts.contains(&keybind.button_groups)
+                    && keybind.chord_pressed(&keybind.button_groups, button_states)
+            } else if !keybind.prefix.starts_with(&self.pending) {
+                false
+            } else if step < keybind.prefix.len() {
+                let chord = &keybind.prefix[step];
+                expected.extend(chord.iter().flatten().copied());
+                if keybind.chord_pressed(chord, button_states) {
+                    advanced = Some(chord.clone());
+                }
+                false
+            } else {
+                expected.extend(keybind.button_groups.iter().flatten().copied());
+                let pressed = keybind.chord_pressed(&keybind.button_groups, button_states);
+                completed |= pressed;
+                pressed
+            };
+            keybind.update(active, now);
+        }
+
+        if completed {
+            self.cancel_pending();
+        } else if let Some(chord) = advanced {
+            // timed from when the chord's last button went down, not from when we got to it
+            let pressed_at = chord
+                .iter()
+                .flatten()
+                .filter_map(|button| button_states.get(button))
+                .filter(|state| state.is_down)
+                .map(|state| state.changed_at)
+                .max();
+            self.pending.push(chord);
+            self.pending_since = Some(pressed_at.unwrap_or(now));
+        } else if !self.pending.is_empty() {
+            // any other key aborts the sequence
+            let stray = button_states.iter().any(|(button, state)| {
+                state.is_pressed()
+                    && !expected.contains(button)
+                    && !button.twin().is_some_and(|twin| expected.contains(&twin))
+            });
+            if stray {
+                self.cancel_pending();
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use crate::*;
+use std::{
+    any::Any,
+    sync::{Arc, RwLock},
+};
+
+use sdl3::{event::Event, video::Window};
+
+#[derive(Debug, Clone, Copy)]
+pub enum Timestep {
+    // one update per frame with the measured frame time
+    Variable,
+    // updates at tick_rate per second, running at most max_steps ticks per frame to catch up
+    // input from frames that run no tick waits for the next one
+    Fixed { tick_rate: f32, max_steps: u32 },
+}
+
+// everything a callback may touch for the window it is being driven for
+pub struct Context<'a> {
+    pub window_id: u32,
+    pub window: &'a Arc<Window>,
+    pub render_context: &'a Arc<RenderContext>,
+    pub assets: &'a Arc<AssetManager>,
+    pub inputs: &'a mut InputManager,
+    pub chrome: &'a RwLock<WindowChrome>,
+    pub shared: &'a mut Shared,
+}
+impl Context<'_> {
+    pub fn window_action(&self, action: WindowAction) -> anyhow::Result<()> {
+        action.apply(self.window, self.render_context)
+    }
+}
+
+// implemented by the user and driven by SQ::run, every callback has an empty default
+pub trait SQApp {
+    // called once per window, before its first update
+    fn on_start(&mut self, ctx: &mut Context) {}
+
+    // called for every event routed to a window, after the window and its inputs handled it
+    fn on_event(&mut self, ctx: &mut Context, event: &Event) {}
+
+    // called once per frame per window, or once per tick with Timestep::Fixed, dt in seconds
+    fn on_update(&mut self, ctx: &mut Context, dt: f32) {}
+
+    // queue draw calls here, the renderer is executed right after
+    // alpha (0.0 ~ 1.0) is how far we are between the last tick and the next,
+    // always 1.0 with Timestep::Variable
+    fn on_render(&mut self, ctx: &mut Context, renderer: &mut Renderer, alpha: f32) {}
+
+    // called when a window is focused, minimised, moved between monitors and so on
+    fn on_window_event(&mut self, ctx: &mut Context, event: &SQWindowEvent) {}
+
+    // called when a window's close button is pressed, return false to keep it open
+    fn on_window_close_requested(&mut self, ctx: &mut Context) -> bool {
+        true
+    }
+
+    // called when files or text from another application are dropped onto a window,
+    // target is the drop target under the cursor that accepts at least one of the items
+    fn on_drop(&mut self, ctx: &mut Context, target: Option<DropTargetKey>, drop: DropSession) {}
+
+    // called for messages sent to this window with Context::send or Context::broadcast
+    fn on_message(&mut self, ctx: &mut Context, from: u32, payload: &dyn Any) {}
+
+    // called on the window under the cursor when a drag from Context::start_drag is released,
+    // drag.hover holds the drop position
+    fn on_drag_drop(&mut self, ctx: &mut Context, drag: DragSession) {}
+
+    // called on the source window when a drag is released outside every window,
+    // e.g. to detach a panel into a new window at drag.screen_position
+    fn on_drag_released_outside(&mut self, ctx: &mut Context, drag: DragSession) {}
+
+    // touch gestures recognised in this window, positions in logical units
+    fn on_gesture(&mut self, ctx: &mut Context, gesture: &Gesture) {}
+
+    // called whenever the clipboard contents change, including by this application
+    fn on_clipboard_changed(&mut self, clipboard: &Clipboard) {}
+
+    // called once a newly connected gamepad has been opened, e.g. to assign it a player
+    fn on_gamepad_added(&mut self, gamepads: &mut GamepadManager, id: u32) {}
+
+    // its buttons have already been released in every window
+    fn on_gamepad_removed(&mut self, gamepads: &mut GamepadManager, id: u32) {}
+
+    // called when a window queued with Shared::spawn_window or Context::spawn_child couldn't be
+    // opened, nothing else reports it
+    fn on_window_spawn_failed(&mut self, builder: &WindowBuilder, error: anyhow::Error) {}
+
+    // called on a global quit request, return false to keep running
+    fn on_close_requested(&mut self) -> bool {
+        true
+    }
+}
@@ -1,5 +1,8 @@
 use crate::*;
-use std::{collections::HashMap, sync::RwLock};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, RwLock},
+};
 
 use anyhow::Context;
 use slotmap::{SlotMap, new_key_type};
@@ -22,6 +25,9 @@ pub struct AssetManager {
     pub shader_assets: RwLock<HashMap<AssetName, ShaderKey>>,
     pub material_assets: RwLock<HashMap<AssetName, MaterialKey>>,
     pub dynamic_render_targets: RwLock<HashMap<RenderTargetName, RenderTargetKey>>,
+    pub font_assets: RwLock<HashMap<AssetName, FontKey>>,
+    // Material::text for each page of a font's atlas, per shader
+    pub font_materials: RwLock<HashMap<(FontKey, ShaderKey), Vec<MaterialKey>>>,
 
     pub vertex_buffers: RwLock<SlotMap<VertexBufferKey, VertexBuffer>>,
     pub index_buffers: RwLock<SlotMap<IndexBufferKey, IndexBuffer>>,
@@ -29,6 +35,8 @@ pub struct AssetManager {
     pub textures: RwLock<SlotMap<TextureKey, SQTexture>>,
     pub shaders: RwLock<SlotMap<ShaderKey, RenderPipeline>>,
     pub materials: RwLock<SlotMap<MaterialKey, Material>>,
+    // a mutex since fonts are Send but not Sync, they can only be used on the main thread anyway
+    pub fonts: Mutex<SlotMap<FontKey, SQFont>>,
 }
 impl AssetManager {
     pub fn new() -> Self {
@@ -37,6 +45,8 @@ impl AssetManager {
             shader_assets: HashMap::new().into(),
             material_assets: HashMap::new().into(),
             dynamic_render_targets: HashMap::new().into(),
+            font_assets: HashMap::new().into(),
+            font_materials: HashMap::new().into(),
 
             vertex_buffers: SlotMap::with_key().into(),
             index_buffers: SlotMap::with_key().into(),
@@ -44,6 +54,7 @@ impl AssetManager {
             textures: SlotMap::with_key().into(),
             shaders: SlotMap::with_key().into(),
             materials: SlotMap::with_key().into(),
+            fonts: SlotMap::with_key().into(),
         }
     }
 
@@ -75,6 +86,138 @@ impl AssetManager {
         self.texture_assets.read().unwrap().get(asset_key).copied()
     }
 
+    // the same file at another size is another font, named "path@size"
+    pub fn load_font(
+        &self,
+        render_context: &RenderContext,
+        path: &str,
+        size: f32,
+    ) -> anyhow::Result<AssetName> {
+        self.load_font_with(render_context, path, size, GlyphMode::Bitmap, 1024)
+    }
+
+    // one size is enough, SDF glyphs are scaled when queued, named "path@size sdf"
+    pub fn load_sdf_font(
+        &self,
+        render_context: &RenderContext,
+        path: &str,
+        size: f32,
+    ) -> anyhow::Result<AssetName> {
+        self.load_font_with(render_context, path, size, GlyphMode::Sdf, 1024)
+    }
+
+    // atlas_size is the width and height of each atlas page, e.g. smaller for a font that only
+    // draws digits or larger for one that draws a whole CJK text
+    pub fn load_font_with(
+        &self,
+        render_context: &RenderContext,
+        path: &str,
+        size: f32,
+        mode: GlyphMode,
+        atlas_size: u32,
+    ) -> anyhow::Result<AssetName> {
+        let name = match mode {
+            GlyphMode::Bitmap => format!("{}@{}", path, size),
+            GlyphMode::Sdf => format!("{}@{} sdf", path, size),
+        };
+        if self.font_assets.read().unwrap().contains_key(&name) {
+            return Ok(name);
+        }
+
+        let font = SQFont::load(
+            render_context,
+            &mut self.textures.write().unwrap(),
+            name.clone(),
+            path,
+            size,
+            mode,
+            atlas_size,
+        )
+        .with_context(|| format!("Failed to load font file: {}", path))?;
+
+        let font_key = self.fonts.lock().unwrap().insert(font);
+
+        self.font_assets
+            .write()
+            .unwrap()
+            .insert(name.clone(), font_key);
+        Ok(name)
+    }
+
+    pub fn get_font_key(&self, asset_key: &AssetName) -> Option<FontKey> {
+        self.font_assets.read().unwrap().get(asset_key).copied()
+    }
+
+    pub fn font_metrics(&self, key: FontKey) -> Option<FontMetrics> {
+        self.fonts.lock().unwrap().get(key).map(|font| font.metrics)
+    }
+
+    // one texture per atlas page, see SQFont::atlases
+    pub fn font_atlases(&self, key: FontKey) -> Vec<TextureKey> {
+        self.fonts
+            .lock()
+            .unwrap()
+            .get(key)
+            .map_or(vec![], |font| font.atlases())
+    }
+
+    // a Material::text per atlas page for Renderer::queue_glyphs, creating the ones for pages
+    // added since the last call, so call it after laying out
+    pub fn font_materials(&self, key: FontKey, shader: ShaderKey) -> Vec<MaterialKey> {
+        let atlases = self.font_atlases(key);
+        let mut font_materials = self.font_materials.write().unwrap();
+        let materials = font_materials.entry((key, shader)).or_default();
+        for atlas in &atlases[materials.len().min(atlases.len())..] {
+            materials.push(self.create_material(Material::text(*atlas, shader)));
+        }
+        materials.clone()
+    }
+
+    // rasterises any glyphs of text that aren't in the atlases yet
+    pub fn layout_run(
+        &self,
+        render_context: &RenderContext,
+        key: FontKey,
+        text: &str,
+    ) -> Vec<PositionedGlyph> {
+        let mut fonts = self.fonts.lock().unwrap();
+        let Some(font) = fonts.get_mut(key) else {
+            return vec![];
+        };
+        font.layout_run(text, render_context, &mut self.textures.write().unwrap())
+    }
+
+    // rasterises any glyphs of text that aren't in the atlases of style's fonts yet
+    pub fn layout_paragraph(
+        &self,
+        render_context: &RenderContext,
+        text: &str,
+        style: &ParagraphStyle,
+    ) -> Paragraph {
+        Paragraph::layout(
+            &mut self.fonts.lock().unwrap(),
+            &mut self.textures.write().unwrap(),
+            render_context,
+            text,
+            style,
+        )
+    }
+
+    pub fn layout_rich_text(
+        &self,
+        render_context: &RenderContext,
+        rich: &RichText,
+        style: &ParagraphStyle,
+    ) -> RichParagraph {
+        RichParagraph::layout(
+            &mut self.fonts.lock().unwrap(),
+            &mut self.textures.write().unwrap(),
+            render_context,
+            rich,
+            style,
+        )
+    }
+
     pub fn create_material(&self, material: Material) -> MaterialKey {
         self.materials.write().unwrap().insert(material)
     }
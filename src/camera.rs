@@ -1,4 +1,4 @@
-use sdl3::{event::Event, keyboard::Keycode, sys::keycode};
+use sdl3::{event::Event, keyboard::Scancode, sys::keycode};
 
 pub struct Camera {
     pub eye: cgmath::Point3<f32>,
@@ -43,6 +43,9 @@ pub struct CameraUniform{
 
 pub struct CameraController {
     speed: f32,
+    pub orbiting: bool, // mouse motion orbits around the target, e.g. while in relative mouse mode
+    pub orbit_sensitivity: f32, // radians per window coordinate
+    orbit: (f32, f32),
     is_forward_pressed: bool,
     is_backward_pressed: bool,
     is_left_pressed: bool,
@@ -51,6 +54,9 @@ pub struct CameraController {
     pub fn new(speed: f32) -> Self {
         Self {
             speed,
+            orbiting: false,
+            orbit_sensitivity: 0.005,
+            orbit: (0.0, 0.0),
             is_forward_pressed: false,
             is_backward_pressed: false,
             is_left_pressed: false,
@@ -58,48 +64,66 @@ pub struct CameraController {
         }
     }
 
+    // physical keys, so the layout doesn't move WASD around
     pub fn process_events(&mut self, event: &Event) {
         match event {
-            Event::KeyDown { keycode: Some(code), ..} => {
+            Event::KeyDown { scancode: Some(code), ..} => {
                 match code {
-                    &Keycode::W => {
+                    &Scancode::W => {
                         self.is_forward_pressed = true;
                     }
-                    &Keycode::A => {
+                    &Scancode::A => {
                         self.is_left_pressed = true;
                     }
-                    &Keycode::S => {
+                    &Scancode::S => {
                         self.is_backward_pressed = true;
                     }
-                    &Keycode::D => {
+                    &Scancode::D => {
                         self.is_right_pressed = true;
                     }
                     _ => {}
                 }
             }
-            Event::KeyUp { keycode: Some(code), ..} => {
+            Event::KeyUp { scancode: Some(code), ..} => {
                 match code {
-                    &Keycode::W => {
+                    &Scancode::W => {
                         self.is_forward_pressed = false; 
                     }
-                    &Keycode::A => {
+                    &Scancode::A => {
                         self.is_left_pressed = false; 
                     }
-                    &Keycode::S => {
+                    &Scancode::S => {
                         self.is_backward_pressed = false; 
                     }
-                    &Keycode::D => {
+                    &Scancode::D => {
                         self.is_right_pressed = false; 
                     }
                     _ => {}
                 }
             }
+            Event::MouseMotion { xrel, yrel, ..} if self.orbiting => {
+                self.orbit.0 += xrel;
+                self.orbit.1 += yrel;
+            }
             _ => {}
         }
     }
 
-    pub fn update_camera(&self, camera: &mut Camera) {
+    pub fn update_camera(&mut self, camera: &mut Camera) {
         use cgmath::InnerSpace;
+        if self.orbit != (0.0, 0.0) {
+            let up = camera.up.normalize();
+            let yaw = cgmath::Matrix3::from_axis_angle(up, cgmath::Rad(-self.orbit.0 * self.orbit_sensitivity));
+            let offset = yaw * (camera.eye - camera.target);
+            let right = offset.cross(up).normalize();
+            let pitch = cgmath::Matrix3::from_axis_angle(right, cgmath::Rad(self.orbit.1 * self.orbit_sensitivity));
+            let pitched = pitch * offset;
+            // stop short of straight up or down, the view flips over past it
+            let offset = if pitched.normalize().dot(up).abs() < 0.99 { pitched } else { offset };
+            camera.eye = camera.target + offset;
+            self.orbit = (0.0, 0.0);
+        }
+
         let forward = camera.target - camera.eye;
         let forward_norm = forward.normalize();
         let forward_mag = forward.magnitude();
@@ -0,0 +1,194 @@
+use crate::*;
+use std::{
+    ffi::c_void,
+    sync::{Arc, RwLock},
+};
+
+use sdl3::{
+    event::{Event, WindowEvent},
+    sys::{rect::SDL_Point, video::*},
+    video::Window,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitRegion {
+    Normal,
+    Draggable,
+    ResizeTopLeft,
+    ResizeTop,
+    ResizeTopRight,
+    ResizeRight,
+    ResizeBottomRight,
+    ResizeBottom,
+    ResizeBottomLeft,
+    ResizeLeft,
+}
+
+// decides what pressing the mouse somewhere on a borderless window does, in logical units
+pub struct WindowChrome {
+    pub drag_regions: Vec<Rectangle<f32>>, // e.g. the title bar
+    pub passthrough_regions: Vec<Rectangle<f32>>, // holes in drag regions, e.g. title bar buttons
+    pub resize_border: f32,                // thickness of the resize edges, 0.0 disables them
+}
+impl WindowChrome {
+    pub fn new(resize_border: f32) -> Self {
+        Self {
+            drag_regions: vec![],
+            passthrough_regions: vec![],
+            resize_border,
+        }
+    }
+
+    pub fn hit_test(&self, point: Vector2<f32>, size: Vector2<f32>, resizable: bool) -> HitRegion {
+        let border = self.resize_border;
+        if resizable && border > 0.0 {
+            let left = point.x < border;
+            let right = point.x >= size.x - border;
+            let top = point.y < border;
+            let bottom = point.y >= size.y - border;
+
+            match (left, right, top, bottom) {
+                (true, _, true, _) => return HitRegion::ResizeTopLeft,
+                (_, true, true, _) => return HitRegion::ResizeTopRight,
+                (true, _, _, true) => return HitRegion::ResizeBottomLeft,
+                (_, true, _, true) => return HitRegion::ResizeBottomRight,
+                (true, ..) => return HitRegion::ResizeLeft,
+                (_, true, ..) => return HitRegion::ResizeRight,
+                (_, _, true, _) => return HitRegion::ResizeTop,
+                (.., true) => return HitRegion::ResizeBottom,
+                _ => {}
+            }
+        }
+
+        if self.passthrough_regions.iter().any(|r| r.contains(point)) {
+            return HitRegion::Normal;
+        }
+        if self.drag_regions.iter().any(|r| r.contains(point)) {
+            return HitRegion::Draggable;
+        }
+        HitRegion::Normal
+    }
+}
+
+// what the native title bar buttons would do, for widgets to invoke
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowAction {
+    Minimize,
+    Maximize,
+    Restore,
+    ToggleMaximize,
+    Close, // goes through on_window_close_requested like the native close button
+}
+impl WindowAction {
+    pub fn apply(self, window: &Window, render_context: &RenderContext) -> anyhow::Result<()> {
+        // Window is a shared handle, the clone refers to the same SDL window
+        let mut window = window.clone();
+        let done = match self {
+            WindowAction::Minimize => window.minimize(),
+            WindowAction::Maximize => window.maximize(),
+            WindowAction::Restore => window.restore(),
+            WindowAction::ToggleMaximize if window.is_maximized() => window.restore(),
+            WindowAction::ToggleMaximize => window.maximize(),
+            WindowAction::Close => {
+                render_context
+                    .sdl_context
+                    .event()?
+                    .push_event(Event::Window {
+                        timestamp: 0, // filled in by SDL
+                        window_id: window.id(),
+                        win_event: WindowEvent::CloseRequested,
+                    })?;
+                true
+            }
+        };
+
+        if done {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Failed to {:?} window: {}",
+                self,
+                sdl3::get_error()
+            ))
+        }
+    }
+}
+
+// keeps SDL's hit test callback pointed at the chrome for as long as the window lives,
+// the unsafe part is contained in here like create_surface
+pub(crate) struct HitTestRegistration {
+    window: Window,
+    chrome: Arc<RwLock<WindowChrome>>,
+}
+impl HitTestRegistration {
+    pub(crate) fn new(window: &Window, chrome: Arc<RwLock<WindowChrome>>) -> anyhow::Result<Self> {
+        // Safety: the registration owns a reference to the chrome and unregisters in Drop,
+        // so the pointer handed to SDL stays valid for as long as the callback can run
+        let registered = unsafe {
+            SDL_SetWindowHitTest(
+                window.raw(),
+                Some(hit_test),
+                Arc::as_ptr(&chrome) as *mut c_void,
+            )
+        };
+        if !registered {
+            return Err(anyhow::anyhow!(
+                "Failed to set window hit test: {}",
+                sdl3::get_error()
+            ));
+        }
+
+        Ok(Self {
+            window: window.clone(),
+            chrome,
+        })
+    }
+}
+impl Drop for HitTestRegistration {
+    fn drop(&mut self) {
+        unsafe {
+            SDL_SetWindowHitTest(self.window.raw(), None, std::ptr::null_mut());
+        }
+    }
+}
+
+unsafe extern "C" fn hit_test(
+    window: *mut SDL_Window,
+    area: *const SDL_Point,
+    data: *mut c_void,
+) -> SDL_HitTestResult {
+    let chrome = unsafe { &*(data as *const RwLock<WindowChrome>) };
+    // never block inside SDL's event processing
+    let Ok(chrome) = chrome.try_read() else {
+        return SDL_HITTEST_NORMAL;
+    };
+
+    let (point, size, resizable) = unsafe {
+        let logical_scale = SDL_GetWindowPixelDensity(window) / SDL_GetWindowDisplayScale(window);
+        let (mut width, mut height) = (0, 0);
+        SDL_GetWindowSize(window, &mut width, &mut height);
+
+        let flags = SDL_GetWindowFlags(window);
+        let resizable = flags & SDL_WINDOW_RESIZABLE != 0
+            && flags & (SDL_WINDOW_MAXIMIZED | SDL_WINDOW_FULLSCREEN) == 0;
+
+        (
+            Vector2::new((*area).x as f32, (*area).y as f32) * logical_scale,
+            Vector2::new(width as f32, height as f32) * logical_scale,
+            resizable,
+        )
+    };
+
+    match chrome.hit_test(point, size, resizable) {
+        HitRegion::Normal => SDL_HITTEST_NORMAL,
+        HitRegion::Draggable => SDL_HITTEST_DRAGGABLE,
+        HitRegion::ResizeTopLeft => SDL_HITTEST_RESIZE_TOPLEFT,
+        HitRegion::ResizeTop => SDL_HITTEST_RESIZE_TOP,
+        HitRegion::ResizeTopRight => SDL_HITTEST_RESIZE_TOPRIGHT,
+        HitRegion::ResizeRight => SDL_HITTEST_RESIZE_RIGHT,
+        HitRegion::ResizeBottomRight => SDL_HITTEST_RESIZE_BOTTOMRIGHT,
+        HitRegion::ResizeBottom => SDL_HITTEST_RESIZE_BOTTOM,
+        HitRegion::ResizeBottomLeft => SDL_HITTEST_RESIZE_BOTTOMLEFT,
+        HitRegion::ResizeLeft => SDL_HITTEST_RESIZE_LEFT,
+    }
+}
@@ -0,0 +1,218 @@
+use crate::*;
+use std::{
+    collections::HashMap,
+    ffi::{CStr, CString, c_char, c_void},
+    io::Cursor,
+};
+
+use anyhow::anyhow;
+use image::{ImageFormat, RgbaImage};
+use sdl3::{VideoSubsystem, clipboard::ClipboardUtil, sys::clipboard::*, sys::stdinc::SDL_free};
+
+const PLAIN_MIME_TYPES: [&str; 2] = ["text/plain;charset=utf-8", "text/plain"];
+const HTML_MIME_TYPE: &str = "text/html";
+const PNG_MIME_TYPE: &str = "image/png";
+
+// text goes through sdl3's wrapper, everything else through SDL's mime type api,
+// the unsafe part is contained in here like create_surface
+pub struct Clipboard {
+    util: ClipboardUtil,
+}
+impl Clipboard {
+    pub fn new(video_subsystem: &VideoSubsystem) -> Self {
+        Self {
+            util: video_subsystem.clipboard(),
+        }
+    }
+
+    pub fn text(&self) -> anyhow::Result<String> {
+        Ok(self.util.clipboard_text()?)
+    }
+
+    pub fn set_text(&self, text: &str) -> anyhow::Result<()> {
+        Ok(self.util.set_clipboard_text(text)?)
+    }
+
+    pub fn has_text(&self) -> bool {
+        self.util.has_clipboard_text()
+    }
+
+    pub fn html(&self) -> Option<String> {
+        let bytes = self.data(HTML_MIME_TYPE)?;
+        Some(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    // plain is what applications that don't understand html get to paste
+    pub fn set_html(&self, html: &str, plain: &str) -> anyhow::Result<()> {
+        let mut offers = HashMap::new();
+        offers.insert(HTML_MIME_TYPE.to_string(), html.as_bytes().to_vec());
+        for mime_type in PLAIN_MIME_TYPES {
+            offers.insert(mime_type.to_string(), plain.as_bytes().to_vec());
+        }
+        self.set_data(offers)
+    }
+
+    pub fn has_html(&self) -> bool {
+        self.has_data(HTML_MIME_TYPE)
+    }
+
+    // whatever image format was copied, it is decoded to rgba
+    pub fn image(&self) -> anyhow::Result<Option<RgbaImage>> {
+        let Some(mime_type) = self
+            .mime_types()
+            .into_iter()
+            .find(|mime_type| ImageFormat::from_mime_type(mime_type).is_some())
+        else {
+            return Ok(None);
+        };
+        let Some(bytes) = self.data(&mime_type) else {
+            return Ok(None);
+        };
+
+        let format = ImageFormat::from_mime_type(&mime_type).unwrap();
+        let image = image::load_from_memory_with_format(&bytes, format)?;
+        Ok(Some(image.to_rgba8()))
+    }
+
+    // offered as png, the format every platform's clipboard understands
+    pub fn set_image(&self, image: &RgbaImage) -> anyhow::Result<()> {
+        let mut png = Cursor::new(vec![]);
+        image.write_to(&mut png, ImageFormat::Png)?;
+
+        let mut offers = HashMap::new();
+        offers.insert(PNG_MIME_TYPE.to_string(), png.into_inner());
+        self.set_data(offers)
+    }
+
+    pub fn has_image(&self) -> bool {
+        self.mime_types()
+            .iter()
+            .any(|mime_type| ImageFormat::from_mime_type(mime_type).is_some())
+    }
+
+    pub fn image_texture(
+        &self,
+        render_context: &RenderContext,
+    ) -> anyhow::Result<Option<SQTexture>> {
+        let Some(image) = self.image()? else {
+            return Ok(None);
+        };
+
+        let texture = SQTexture::from_image(
+            &render_context.device,
+            &render_context.queue,
+            &image.into(),
+            Some("Clipboard"),
+        )?;
+        Ok(Some(texture))
+    }
+
+    pub fn mime_types(&self) -> Vec<String> {
+        let mut count = 0;
+        unsafe {
+            let list = SDL_GetClipboardMimeTypes(&mut count);
+            if list.is_null() {
+                return vec![];
+            }
+
+            let mime_types = (0..count)
+                .map(|i| CStr::from_ptr(*list.add(i)).to_string_lossy().into_owned())
+                .collect();
+            // the strings live in the same allocation as the list
+            SDL_free(list as *mut c_void);
+            mime_types
+        }
+    }
+
+    pub fn has_data(&self, mime_type: &str) -> bool {
+        let Ok(mime_type) = CString::new(mime_type) else {
+            return false;
+        };
+        unsafe { SDL_HasClipboardData(mime_type.as_ptr()) }
+    }
+
+    pub fn data(&self, mime_type: &str) -> Option<Vec<u8>> {
+        let mime_type = CString::new(mime_type).ok()?;
+        let mut size = 0;
+        unsafe {
+            let data = SDL_GetClipboardData(mime_type.as_ptr(), &mut size);
+            if data.is_null() {
+                return None;
+            }
+
+            let bytes = std::slice::from_raw_parts(data as *const u8, size).to_vec();
+            SDL_free(data);
+            Some(bytes)
+        }
+    }
+
+    // replaces the clipboard with the given data per mime type
+    pub fn set_data(&self, offers: HashMap<String, Vec<u8>>) -> anyhow::Result<()> {
+        // the only cases SDL refuses before taking ownership of the offers, past them it calls
+        // cleanup_data itself even when setting the data fails
+        if offers.is_empty() {
+            return Err(anyhow!("No clipboard data to set"));
+        }
+        // Safety: no preconditions
+        if unsafe { sdl3::sys::init::SDL_WasInit(sdl3::sys::init::SDL_INIT_VIDEO) } == 0 {
+            return Err(anyhow!(
+                "Failed to set clipboard data: video is not initialised"
+            ));
+        }
+
+        let mime_types = offers
+            .keys()
+            .map(|mime_type| CString::new(mime_type.as_str()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut mime_type_ptrs: Vec<*const c_char> = mime_types
+            .iter()
+            .map(|mime_type| mime_type.as_ptr())
+            .collect();
+
+        // Safety: SDL copies the mime type list, and owns the offers from here on until it calls
+        // cleanup_data
+        let offers = Box::into_raw(Box::new(offers));
+        let set = unsafe {
+            SDL_SetClipboardData(
+                Some(provide_data),
+                Some(cleanup_data),
+                offers as *mut c_void,
+                mime_type_ptrs.as_mut_ptr(),
+                mime_type_ptrs.len(),
+            )
+        };
+
+        if set {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Failed to set clipboard data: {}",
+                sdl3::get_error()
+            ))
+        }
+    }
+}
+
+unsafe extern "C" fn provide_data(
+    userdata: *mut c_void,
+    mime_type: *const c_char,
+    size: *mut usize,
+) -> *const c_void {
+    let offers = unsafe { &*(userdata as *const HashMap<String, Vec<u8>>) };
+    let mime_type = unsafe { CStr::from_ptr(mime_type) }.to_string_lossy();
+
+    match offers.get(mime_type.as_ref()) {
+        Some(data) => {
+            unsafe { *size = data.len() };
+            data.as_ptr() as *const c_void
+        }
+        None => {
+            unsafe { *size = 0 };
+            std::ptr::null()
+        }
+    }
+}
+
+unsafe extern "C" fn cleanup_data(userdata: *mut c_void) {
+    drop(unsafe { Box::from_raw(userdata as *mut HashMap<String, Vec<u8>>) });
+}
@@ -0,0 +1,139 @@
+use crate::*;
+use std::path::PathBuf;
+
+use cgmath::Vector2;
+use sdl3::event::Event;
+use slotmap::SlotMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DropItem {
+    File(PathBuf),
+    Text(String),
+}
+
+// everything dropped onto a window in one go, items only arrive once the user lets go
+#[derive(Debug, Clone, Default)]
+pub struct DropSession {
+    pub items: Vec<DropItem>,
+    pub position: Option<Vector2<f32>>, // logical, None while the cursor position is unknown
+}
+impl DropSession {
+    pub fn files(&self) -> impl Iterator<Item = &PathBuf> {
+        self.items.iter().filter_map(|item| match item {
+            DropItem::File(path) => Some(path),
+            _ => None,
+        })
+    }
+
+    pub fn texts(&self) -> impl Iterator<Item = &str> {
+        self.items.iter().filter_map(|item| match item {
+            DropItem::Text(text) => Some(text.as_str()),
+            _ => None,
+        })
+    }
+}
+
+slotmap::new_key_type! { pub struct DropTargetKey; }
+pub struct DropTarget {
+    pub bounds: Rectangle<f32>, // logical
+    pub z_order: i32,           // the highest target under the cursor wins
+    pub accepts_files: bool,
+    pub accepts_text: bool,
+}
+impl DropTarget {
+    fn accepts(&self, item: &DropItem) -> bool {
+        match item {
+            DropItem::File(_) => self.accepts_files,
+            DropItem::Text(_) => self.accepts_text,
+        }
+    }
+}
+
+pub struct DropManager {
+    pub targets: SlotMap<DropTargetKey, DropTarget>,
+    pub session: Option<DropSession>,
+    pub hovered: Option<DropTargetKey>, // for highlighting the target under the cursor
+    completed: Option<(Option<DropTargetKey>, DropSession)>,
+}
+impl DropManager {
+    pub fn new() -> Self {
+        Self {
+            targets: SlotMap::with_key(),
+            session: None,
+            hovered: None,
+            completed: None,
+        }
+    }
+
+    pub fn add_target(&mut self, target: DropTarget) -> DropTargetKey {
+        self.targets.insert(target)
+    }
+
+    pub fn remove_target(&mut self, key: DropTargetKey) {
+        self.targets.remove(key);
+        if self.hovered == Some(key) {
+            self.hovered = None;
+        }
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.session.is_some()
+    }
+
+    pub fn target_at(&self, position: Vector2<f32>) -> Option<DropTargetKey> {
+        self.targets
+            .iter()
+            .filter(|(_, target)| target.bounds.contains(position))
+            .max_by_key(|(_, target)| target.z_order)
+            .map(|(key, _)| key)
+    }
+
+    // called by SQ for every SDL_EVENT_DROP_POSITION while a drag hovers the window
+    pub fn update_hover(&mut self, position: Vector2<f32>) {
+        if let Some(session) = &mut self.session {
+            session.position = Some(position);
+            self.hovered = self.target_at(position);
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        match event {
+            Event::DropBegin { .. } => {
+                self.session = Some(DropSession::default());
+            }
+            Event::DropFile { filename, .. } => {
+                // some platforms skip DropBegin for single items
+                let session = self.session.get_or_insert_with(DropSession::default);
+                session.items.push(DropItem::File(PathBuf::from(filename)));
+            }
+            // sdl3 names the text field filename as well
+            Event::DropText { filename, .. } => {
+                let session = self.session.get_or_insert_with(DropSession::default);
+                session.items.push(DropItem::Text(filename.clone()));
+            }
+            Event::DropComplete { .. } => {
+                let hovered = self.hovered.take();
+                // a session without items means the drag left the window
+                if let Some(session) = self.session.take().filter(|s| !s.items.is_empty()) {
+                    let target = hovered.filter(|key| {
+                        let target = &self.targets[*key];
+                        session.items.iter().any(|item| target.accepts(item))
+                    });
+                    self.completed = Some((target, session));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // the finished drop and the target it landed on, if any, once per drop
+    pub fn take_completed(&mut self) -> Option<(Option<DropTargetKey>, DropSession)> {
+        self.completed.take()
+    }
+
+    pub fn reset(&mut self) {
+        self.session = None;
+        self.hovered = None;
+        self.completed = None;
+    }
+}
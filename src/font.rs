@@ -0,0 +1,504 @@
+use crate::*;
+use std::{collections::HashMap, mem::ManuallyDrop};
+
+use anyhow::anyhow;
+use cgmath::Vector2;
+use image::{DynamicImage, RgbaImage};
+use rustybuzz::ttf_parser::{self, GlyphId};
+use sdl3::{iostream::IOStream, pixels::PixelFormat, surface::Surface, ttf::Hinting};
+use slotmap::{SlotMap, new_key_type};
+
+new_key_type! {
+    pub struct FontKey;
+}
+
+// in pixels at the size the font was loaded with, y grows down from the baseline
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FontMetrics {
+    pub ascent: f32,  // baseline to the top of the tallest glyph
+    pub descent: f32, // baseline to the bottom of the lowest glyph, positive
+    pub line_gap: f32,
+    pub line_height: f32, // ascent + descent + line_gap, from one baseline to the next
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Glyph {
+    pub uv_rect: Rectangle<f32>, // in the font's atlas, empty for glyphs with nothing to draw
+    pub size: Vector2<f32>,
+    pub offset: Vector2<f32>, // from the pen position on the baseline to the top left corner
+    pub advance: f32,
+    pub page: usize, // the atlas page uv_rect is on, see SQFont::atlases
+}
+
+// a glyph placed on a line, positions are relative to the start of the run's baseline, the
+// glyph's offset and advance include what shaping moved it by
+#[derive(Clone, Copy, PartialEq)]
+pub struct PositionedGlyph {
+    pub ch: char,               // the first character of the text it was shaped from
+    pub position: Vector2<f32>, // top left corner
+    pub glyph: Glyph,
+}
+
+// one glyph of a shaped run, in pixels at the font's size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapedGlyph {
+    pub id: u16,
+    pub cluster: usize, // byte offset in the run of the first character it was shaped from
+    pub advance: f32,
+    pub offset: Vector2<f32>, // from the pen position, y grows down
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlyphMode {
+    Bitmap, // coverage in alpha, sharp at the loaded size only
+    Sdf,    // signed distance in alpha, drawn with a material built from SDF_SHADER
+}
+
+// glyphs are packed left to right in rows as tall as the tallest glyph in them, once a page is
+// full the font starts another
+struct AtlasPage {
+    texture: TextureKey,
+    size: u32,
+    cursor: Vector2<u32>,
+    row_height: u32,
+}
+impl AtlasPage {
+    // padding is kept clear on every side, so filtering doesn't bleed into the neighbours
+    fn allocate(&mut self, width: u32, height: u32, padding: u32) -> Option<Vector2<u32>> {
+        let (padded_width, padded_height) = (width + padding, height + padding);
+        if self.cursor.x + padded_width + padding > self.size {
+            self.cursor = Vector2::new(0, self.cursor.y + self.row_height);
+            self.row_height = 0;
+        }
+        if self.cursor.x + padded_width + padding > self.size
+            || self.cursor.y + padded_height + padding > self.size
+        {
+            return None;
+        }
+        let position = self.cursor + Vector2::new(padding, padding);
+        self.cursor.x += padded_width;
+        self.row_height = self.row_height.max(padded_height);
+        Some(position)
+    }
+}
+
+// a TTF or OTF font at one size, glyphs are rasterised into the atlas the first time they're used
+pub struct SQFont {
+    pub name: AssetName,
+    pub size: f32, // points, which SDL_ttf treats as pixels
+    pub metrics: FontMetrics,
+    pub mode: GlyphMode,
+    pub atlas_size: u32, // of each page, glyphs too big for one get a page of their own
+    font: ManuallyDrop<sdl3::ttf::Font<'static>>, // only through SQFont::font
+    data: Vec<u8>,       // the font file, for shaping and for SDL_ttf to read the font from
+    glyphs: HashMap<u16, Glyph>, // by glyph id
+    pages: Vec<AtlasPage>,
+}
+// Safety: fonts are only loaded on the main thread, every later use goes through SQFont::font
+// which panics on any other, and one dropped elsewhere is leaked instead of closed, so moving an
+// SQFont to another thread never has SDL_ttf touch it there
+unsafe impl Send for SQFont {}
+impl Drop for SQFont {
+    fn drop(&mut self) {
+        if on_main_thread() {
+            // Safety: the font isn't used again
+            unsafe { ManuallyDrop::drop(&mut self.font) };
+        }
+    }
+}
+impl SQFont {
+    pub fn load(
+        render_context: &RenderContext,
+        textures: &mut slotmap::SlotMap<TextureKey, SQTexture>,
+        name: AssetName,
+        path: &str,
+        size: f32,
+        mode: GlyphMode,
+        atlas_size: u32,
+    ) -> anyhow::Result<Self> {
+        if atlas_size == 0 {
+            return Err(anyhow!("Atlas size must be at least 1"));
+        }
+        if !on_main_thread() {
+            return Err(anyhow!("Fonts can only be loaded on the main thread"));
+        }
+        let data = std::fs::read(path)?;
+        if ttf_parser::Face::parse(&data, 0).is_err() {
+            return Err(anyhow!("Failed to parse {}", path));
+        }
+        // Safety: SDL_ttf reads the font from data for as long as it's open, data is never
+        // changed so its buffer stays put when the SQFont moves, and Drop closes the font before
+        // the fields are dropped
+        let bytes: &'static [u8] = unsafe { std::slice::from_raw_parts(data.as_ptr(), data.len()) };
+        // the font keeps SDL_ttf initialised for as long as it lives
+        let mut font =
+            sdl3::ttf::init()?.load_font_from_iostream(IOStream::from_bytes(bytes)?, size)?;
+        // glyphs go where shaping puts them, which is unhinted, so the bitmaps can't be hinted
+        // either or they'd no longer match the outline's bounds
+        font.set_hinting(Hinting::NONE);
+        if mode == GlyphMode::Sdf {
+            // Safety: the font is alive for the duration of the call
+            if !unsafe { sdl3::ttf::sys::TTF_SetFontSDF(font.raw(), true) } {
+                return Err(anyhow!("Failed to enable SDF: {}", sdl3::get_error()));
+            }
+        }
+
+        let ascent = font.ascent() as f32;
+        let descent = -font.descent() as f32;
+        let line_height = font.recommended_line_spacing() as f32;
+        let metrics = FontMetrics {
+            ascent,
+            descent,
+            line_gap: (line_height - ascent - descent).max(0.0),
+            line_height,
+        };
+
+        let mut font = Self {
+            name,
+            size,
+            metrics,
+            mode,
+            atlas_size,
+            font: ManuallyDrop::new(font),
+            data,
+            glyphs: HashMap::new(),
+            pages: vec![],
+        };
+        // materials can be made for the first page before anything is drawn
+        font.add_page(render_context, textures, atlas_size)?;
+        Ok(font)
+    }
+
+    // SDL_ttf may only use a font on the main thread
+    fn font(&self) -> &sdl3::ttf::Font<'static> {
+        assert!(
+            on_main_thread(),
+            "Font {} used off the main thread",
+            self.name
+        );
+        &self.font
+    }
+
+    fn face(&self) -> ttf_parser::Face<'_> {
+        ttf_parser::Face::parse(&self.data, 0).expect("parsed when the font was loaded")
+    }
+
+    // from font units to pixels, SDL_ttf sizes the font at 72 DPI so a point is a pixel
+    fn scale(&self, face: &ttf_parser::Face) -> f32 {
+        self.size / face.units_per_em() as f32
+    }
+
+    // the textures a material has to sample to draw the font's glyphs, one per page in the order
+    // of Glyph::page, more are added as glyphs are rasterised
+    pub fn atlases(&self) -> Vec<TextureKey> {
+        self.pages.iter().map(|page| page.texture).collect()
+    }
+
+    // white glyphs on transparent, for a material to tint
+    fn add_page(
+        &mut self,
+        render_context: &RenderContext,
+        textures: &mut SlotMap<TextureKey, SQTexture>,
+        size: u32,
+    ) -> anyhow::Result<()> {
+        let max_size = render_context.device.limits().max_texture_dimension_2d;
+        if size > max_size {
+            return Err(anyhow!(
+                "Atlas page of {} would be {} pixels, more than the {} allowed",
+                self.name,
+                size,
+                max_size
+            ));
+        }
+
+        let blank = DynamicImage::ImageRgba8(RgbaImage::new(size, size));
+        let label = format!("Glyph Atlas {} {}", self.name, self.pages.len());
+        let texture = SQTexture::from_image(
+            &render_context.device,
+            &render_context.queue,
+            &blank,
+            Some(&label),
+        )?;
+        self.pages.push(AtlasPage {
+            texture: textures.insert(texture),
+            size,
+            cursor: Vector2::new(0, 0),
+            row_height: 0,
+        });
+        Ok(())
+    }
+
+    // the glyph's own bitmap, without the line box render_char would put around it
+    fn rasterise(&self, id: u16) -> anyhow::Result<(u32, u32, Vec<u8>)> {
+        // Safety: the font is alive for the duration of the call, the surface is a new one that
+        // Surface frees when dropped
+        let surface = unsafe {
+            sdl3::ttf::sys::TTF_GetGlyphImageForIndex(
+                self.font().raw(),
+                id as u32,
+                std::ptr::null_mut(),
+            )
+        };
+        if surface.is_null() {
+            return Err(anyhow!(
+                "Failed to render glyph {}: {}",
+                id,
+                sdl3::get_error()
+            ));
+        }
+        let surface = unsafe { Surface::from_ll(surface) }.convert_format(
+            PixelFormat::try_from(sdl3::sys::pixels::SDL_PIXELFORMAT_RGBA32)?,
+        )?;
+
+        let (width, height) = (surface.width(), surface.height());
+        let pixels = surface.with_lock(|pixels| {
+            pixels
+                .chunks(surface.pitch() as usize)
+                .flat_map(|row| &row[..width as usize * 4])
+                .copied()
+                .collect::<Vec<u8>>()
+        });
+        Ok((width, height, pixels))
+    }
+
+    // None for characters the font has no glyph for
+    pub fn glyph_id(&self, ch: char) -> Option<u16> {
+        self.face()
+            .glyph_index(ch)
+            .map(|id| id.0)
+            .filter(|id| *id != 0)
+    }
+
+    pub fn has_glyph(&self, ch: char) -> bool {
+        self.glyph_id(ch).is_some()
+    }
+
+    // unshaped, 0.0 for characters the font has no glyph for
+    pub fn advance(&self, ch: char) -> f32 {
+        let face = self.face();
+        self.glyph_id(ch)
+            .and_then(|id| face.glyph_hor_advance(GlyphId(id)))
+            .map_or(0.0, |advance| advance as f32 * self.scale(&face))
+    }
+
+    // text is one direction and one script, before and after are the text around it, which
+    // shaping looks at for contextual forms, e.g. Arabic letters joining across a change of
+    // style, glyphs come back in visual order and characters the font doesn't have get glyph 0
+    pub fn shape(&self, text: &str, before: &str, after: &str, rtl: bool) -> Vec<ShapedGlyph> {
+        let face = rustybuzz::Face::from_face(self.face());
+        let scale = self.scale(&face);
+        let mut buffer = rustybuzz::UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.set_pre_context(before);
+        buffer.set_post_context(after);
+        buffer.set_direction(if rtl {
+            rustybuzz::Direction::RightToLeft
+        } else {
+            rustybuzz::Direction::LeftToRight
+        });
+        // the script comes from the text
+        buffer.guess_segment_properties();
+
+        let shaped = rustybuzz::shape(&face, &[], buffer);
+        shaped
+            .glyph_infos()
+            .iter()
+            .zip(shaped.glyph_positions())
+            .map(|(info, position)| ShapedGlyph {
+                id: info.glyph_id as u16,
+                cluster: info.cluster as usize,
+                advance: position.x_advance as f32 * scale,
+                offset: Vector2::new(position.x_offset as f32, -position.y_offset as f32) * scale,
+            })
+            .collect()
+    }
+
+    // rasterises the glyph on first use, glyphs without an outline, e.g. bitmap emoji, have
+    // nothing to draw
+    pub fn glyph(
+        &mut self,
+        id: u16,
+        render_context: &RenderContext,
+        textures: &mut SlotMap<TextureKey, SQTexture>,
+    ) -> anyhow::Result<Glyph> {
+        if let Some(glyph) = self.glyphs.get(&id) {
+            return Ok(*glyph);
+        }
+
+        let face = self.face();
+        if id >= face.number_of_glyphs() {
+            return Err(anyhow!("No glyph {} in {}", id, self.name));
+        }
+        let scale = self.scale(&face);
+        let bounds = face.glyph_bounding_box(GlyphId(id));
+        let advance = face.glyph_hor_advance(GlyphId(id)).unwrap_or(0) as f32 * scale;
+        // the bitmap covers the outline's bounds rounded out to whole pixels
+        let offset = bounds.map_or(Vector2::new(0.0, 0.0), |bounds| {
+            Vector2::new(
+                (bounds.x_min as f32 * scale).floor(),
+                -(bounds.y_max as f32 * scale).ceil(),
+            )
+        });
+        let mut glyph = Glyph {
+            uv_rect: Rectangle {
+                x: 0.0,
+                y: 0.0,
+                width: 0.0,
+                height: 0.0,
+            },
+            size: Vector2::new(0.0, 0.0),
+            offset,
+            advance,
+            page: 0,
+        };
+        if self.mode == GlyphMode::Sdf {
+            // the distance field reaches the spread past the outline on every side
+            glyph.offset -= Vector2::new(SDF_SPREAD, SDF_SPREAD);
+        }
+
+        // whitespace has an advance but nothing to draw
+        if bounds.is_some_and(|bounds| bounds.width() > 0 && bounds.height() > 0) {
+            let (width, height, pixels) = self.rasterise(id)?;
+
+            // a pixel for filtering, SDF glyphs also have their shadow looked up as far as
+            // SDF_SPREAD away
+            let padding = match self.mode {
+                GlyphMode::Bitmap => 1,
+                GlyphMode::Sdf => SDF_SPREAD as u32 + 1,
+            };
+            let allocated = self
+                .pages
+                .last_mut()
+                .and_then(|page| page.allocate(width, height, padding));
+            let origin = match allocated {
+                Some(origin) => origin,
+                None => {
+                    let needed = (width.max(height) + 2 * padding).next_power_of_two();
+                    self.add_page(render_context, textures, self.atlas_size.max(needed))?;
+                    self.pages
+                        .last_mut()
+                        .and_then(|page| page.allocate(width, height, padding))
+                        .ok_or(anyhow!("Glyph {} of {} fits no page", id, self.name))?
+                }
+            };
+            glyph.page = self.pages.len() - 1;
+            let page = &self.pages[glyph.page];
+
+            let atlas = textures
+                .get(page.texture)
+                .ok_or(anyhow!("Glyph atlas of {} was removed", self.name))?;
+            render_context.queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &atlas.texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: origin.x,
+                        y: origin.y,
+                        z: 0,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &pixels,
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(width * 4),
+                    rows_per_image: Some(height),
+                },
+                wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+            );
+
+            let page_size = page.size as f32;
+            glyph.uv_rect = Rectangle {
+                x: origin.x as f32 / page_size,
+                y: origin.y as f32 / page_size,
+                width: width as f32 / page_size,
+                height: height as f32 / page_size,
+            };
+            glyph.size = Vector2::new(width as f32, height as f32);
+        }
+
+        self.glyphs.insert(id, glyph);
+        Ok(glyph)
+    }
+
+    // one line of left to right text, shaped, see Paragraph for anything else
+    pub fn layout_run(
+        &mut self,
+        text: &str,
+        render_context: &RenderContext,
+        textures: &mut SlotMap<TextureKey, SQTexture>,
+    ) -> Vec<PositionedGlyph> {
+        let mut glyphs = vec![];
+        let mut pen = 0.0;
+        for shaped in self.shape(text, "", "", false) {
+            if let Ok(mut glyph) = self.glyph(shaped.id, render_context, textures) {
+                glyph.offset += shaped.offset;
+                glyph.advance = shaped.advance;
+                glyphs.push(PositionedGlyph {
+                    ch: text[shaped.cluster..].chars().next().unwrap_or('\u{FFFD}'),
+                    position: Vector2::new(pen, 0.0) + glyph.offset,
+                    glyph,
+                });
+            }
+            pen += shaped.advance;
+        }
+        glyphs
+    }
+}
+
+fn on_main_thread() -> bool {
+    // Safety: no preconditions
+    unsafe { sdl3::sys::init::SDL_IsMainThread() }
+}
+
+// the width of a run, from the first pen position to the last advance
+pub fn run_width(glyphs: &[PositionedGlyph]) -> f32 {
+    glyphs.last().map_or(0.0, |last| {
+        last.position.x - last.glyph.offset.x + last.glyph.advance
+    })
+}
+
+impl Renderer {
+    // each glyph becomes a sprite, materials holds one per atlas page (AssetManager::font_materials)
+    // and glyphs on pages without one are left out, origin is where the run's baseline starts,
+    // scale above 1.0 only stays sharp for SDF fonts
+    #[allow(clippy::too_many_arguments)]
+    pub fn queue_glyphs(
+        &mut self,
+        glyphs: &[PositionedGlyph],
+        materials: &[MaterialKey],
+        origin: Vector2<f32>,
+        scale: f32,
+        z_order: f32,
+        uniforms: MaterialUniforms,
+        pass_name: RenderPassName,
+    ) {
+        for positioned in glyphs {
+            if positioned.glyph.size == Vector2::new(0.0, 0.0) {
+                continue;
+            }
+            let Some(material) = materials.get(positioned.glyph.page) else {
+                continue;
+            };
+            self.queue(
+                *material,
+                Mapping::Sprite {
+                    uv_rect: positioned.glyph.uv_rect,
+                },
+                Transform::Sprite {
+                    position: origin + positioned.position * scale,
+                    rotation: 0.0,
+                    scale: positioned.glyph.size * scale,
+                    z_order,
+                },
+                uniforms.clone(),
+                pass_name.clone(),
+                true,
+            );
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use crate::*;
+use std::{collections::HashMap, time::Duration};
+
+use cgmath::{InnerSpace, Vector2};
+use sdl3::{
+    Sdl,
+    event::Event,
+    gamepad::{Gamepad, GamepadType},
+};
+
+pub use sdl3::gamepad::{Axis as GamepadAxis, Button as GamepadButton};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AxisDirection {
+    Positive, // right, down, or a trigger being pulled
+    Negative, // left, up
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stick {
+    Left,
+    Right,
+}
+
+pub struct GamepadDevice {
+    pub id: u32, // SDL's joystick instance id, stable for as long as the device stays connected
+    pub name: String,
+    pub kind: GamepadType,
+    pub axes: HashMap<GamepadAxis, f32>, // raw, -1.0 ~ 1.0 for sticks and 0.0 ~ 1.0 for triggers
+    gamepad: Gamepad,
+}
+impl GamepadDevice {
+    pub fn player_index(&self) -> Option<u16> {
+        self.gamepad.player_index()
+    }
+
+    pub fn is_down(&self, button: GamepadButton) -> bool {
+        self.gamepad.button(button)
+    }
+
+    // strength is 0.0 ~ 1.0 for the low and high frequency motors
+    pub fn rumble(&mut self, low: f32, high: f32, duration: Duration) -> anyhow::Result<()> {
+        let to_u16 = |strength: f32| (strength.clamp(0.0, 1.0) * u16::MAX as f32) as u16;
+        self.gamepad
+            .set_rumble(to_u16(low), to_u16(high), duration.as_millis() as u32)?;
+        Ok(())
+    }
+}
+
+// opens every gamepad as it is plugged in, the analog state lives here while the
+// digital side goes through each window's InputManager as Button::Gamepad
+pub struct GamepadManager {
+    subsystem: sdl3::GamepadSubsystem,
+    pub devices: HashMap<u32, GamepadDevice>,
+    pub stick_deadzone: f32, // radial, applied to both axes of a stick together
+    pub trigger_deadzone: f32, // 0.0 ~ 1.0
+}
+impl GamepadManager {
+    pub fn new(sdl_context: &Sdl) -> anyhow::Result<Self> {
+        Ok(Self {
+            subsystem: sdl_context.gamepad()?,
+            devices: HashMap::new(),
+            stick_deadzone: 0.15,
+            trigger_deadzone: 0.05,
+        })
+    }
+
+    // joysticks SDL doesn't recognise as gamepads can be made to work with an SDL mapping string
+    pub fn add_mapping(&self, mapping: &str) -> anyhow::Result<()> {
+        self.subsystem.add_mapping(mapping)?;
+        Ok(())
+    }
+
+    pub fn device(&self, id: u32) -> Option<&GamepadDevice> {
+        self.devices.get(&id)
+    }
+
+    pub fn device_mut(&mut self, id: u32) -> Option<&mut GamepadDevice> {
+        self.devices.get_mut(&id)
+    }
+
+    // deadzone applied, rescaled so the output still covers the full range
+    pub fn axis(&self, id: u32, axis: GamepadAxis) -> f32 {
+        match axis {
+            GamepadAxis::TriggerLeft | GamepadAxis::TriggerRight => {
+                let value = self.raw_axis(id, axis);
+                rescale(value, self.trigger_deadzone)
+            }
+            GamepadAxis::LeftX => self.stick(id, Stick::Left).x,
+            GamepadAxis::LeftY => self.stick(id, Stick::Left).y,
+            GamepadAxis::RightX => self.stick(id, Stick::Right).x,
+            GamepadAxis::RightY => self.stick(id, Stick::Right).y,
+        }
+    }
+
+    pub fn stick(&self, id: u32, stick: Stick) -> Vector2<f32> {
+        let (x, y) = match stick {
+            Stick::Left => (GamepadAxis::LeftX, GamepadAxis::LeftY),
+            Stick::Right => (GamepadAxis::RightX, GamepadAxis::RightY),
+        };
+        let raw = Vector2::new(self.raw_axis(id, x), self.raw_axis(id, y));
+
+        let magnitude = raw.magnitude();
+        if magnitude <= self.stick_deadzone {
+            return Vector2::new(0.0, 0.0);
+        }
+        raw / magnitude * rescale(magnitude.min(1.0), self.stick_deadzone)
+    }
+
+    // the strongest value across all connected devices, for single player setups
+    pub fn any_axis(&self, axis: GamepadAxis) -> f32 {
+        self.devices
+            .keys()
+            .map(|id| self.axis(*id, axis))
+            .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+            .unwrap_or(0.0)
+    }
+
+    pub fn any_stick(&self, stick: Stick) -> Vector2<f32> {
+        self.devices
+            .keys()
+            .map(|id| self.stick(*id, stick))
+            .max_by(|a, b| a.magnitude2().total_cmp(&b.magnitude2()))
+            .unwrap_or(Vector2::new(0.0, 0.0))
+    }
+
+    fn raw_axis(&self, id: u32, axis: GamepadAxis) -> f32 {
+        self.devices
+            .get(&id)
+            .and_then(|device| device.axes.get(&axis))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    pub fn handle_event(&mut self, event: &Event) -> anyhow::Result<()> {
+        match event {
+            Event::ControllerDeviceAdded { which, .. } => {
+                let gamepad = self.subsystem.open(*which)?;
+                self.devices.insert(
+                    *which,
+                    GamepadDevice {
+                        id: *which,
+                        name: gamepad.name().unwrap_or_default(),
+                        kind: gamepad.r#type(),
+                        axes: HashMap::new(),
+                        gamepad,
+                    },
+                );
+            }
+            Event::ControllerDeviceRemoved { which, .. } => {
+                self.devices.remove(which);
+            }
+            Event::ControllerAxisMotion {
+                which, axis, value, ..
+            } => {
+                if let Some(device) = self.devices.get_mut(which) {
+                    device.axes.insert(*axis, normalize_axis(*value));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn normalize_axis(value: i16) -> f32 {
+    (value as f32 / i16::MAX as f32).clamp(-1.0, 1.0)
+}
+
+fn rescale(value: f32, deadzone: f32) -> f32 {
+    if value.abs() <= deadzone {
+        return 0.0;
+    }
+    value.signum() * (value.abs() - deadzone) / (1.0 - deadzone)
+}
@@ -1,22 +1,134 @@
 use crate::*;
-use std::collections::{HashMap, HashSet, VecDeque, hash_map::Entry};
+use std::{
+    collections::{HashMap, HashSet, VecDeque, hash_map::Entry},
+    time::Duration,
+};
 
 use cgmath::*;
 use sdl3::{event::*, keyboard::*, mouse::*, pen::*};
-use slotmap::SlotMap;
 use smart_default::SmartDefault;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Button {
-    Key(Keycode),
+    Key(Keycode),       // the character the key produces on the current layout
+    Scancode(Scancode), // the physical key, WASD stays WASD on AZERTY
     Mouse(MouseButton),
     Pen(u8),
+    Gamepad(GamepadButton), // held while any connected gamepad holds it
+    GamepadAxis(GamepadAxis, AxisDirection), // held while the axis is past InputManager::axis_threshold
+}
+// is_down simply means current state, pressed/released means it also changed this tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ButtonState {
+    pub is_down: bool,
+    pub ticks: u32, // update ticks since the last change, 0 on the tick it changed
+    pub changed_at: u64, // SDL event timestamp of the last change, nanoseconds
+    pub pressed_at: Option<u64>,
+    pub previous_pressed_at: Option<u64>, // the press before pressed_at, for double clicks
+}
+impl ButtonState {
+    pub fn new(is_down: bool, timestamp: u64) -> Self {
+        Self {
+            is_down,
+            ticks: 0,
+            changed_at: timestamp,
+            pressed_at: is_down.then_some(timestamp),
+            previous_pressed_at: None,
+        }
+    }
+
+    // up since forever, e.g. a keybind that hasn't been active yet
+    pub fn idle() -> Self {
+        Self {
+            is_down: false,
+            ticks: u32::MAX,
+            changed_at: 0,
+            pressed_at: None,
+            previous_pressed_at: None,
+        }
+    }
+
+    pub fn is_pressed(&self) -> bool {
+        self.is_down && self.ticks == 0
+    }
+
+    pub fn is_released(&self) -> bool {
+        !self.is_down && self.ticks == 0
+    }
+
+    // repeats of the current state (key repeat) are ignored
+    pub fn set(&mut self, is_down: bool, timestamp: u64) {
+        if is_down == self.is_down {
+            return;
+        }
+        if is_down {
+            self.previous_pressed_at = self.pressed_at;
+            self.pressed_at = Some(timestamp);
+        }
+        self.is_down = is_down;
+        self.ticks = 0;
+        self.changed_at = timestamp;
+    }
+
+    pub fn tick(&mut self) {
+        self.ticks = self.ticks.saturating_add(1);
+    }
+
+    // time since the last press or release
+    pub fn elapsed(&self, now: u64) -> Duration {
+        Duration::from_nanos(now.saturating_sub(self.changed_at))
+    }
+
+    pub fn held_for(&self, now: u64) -> Option<Duration> {
+        self.is_down.then(|| self.elapsed(now))
+    }
+
+    // pressed this tick, and the press before it was at most interval ago
+    pub fn is_double_press(&self, interval: Duration) -> bool {
+        match (self.previous_pressed_at, self.pressed_at) {
+            (Some(previous), Some(pressed)) if self.is_pressed() => {
+                Duration::from_nanos(pressed.saturating_sub(previous)) <= interval
+            }
+            _ => false,
+        }
+    }
+}
+
+// SDL's event clock, the one ButtonState timestamps are on
+pub fn sdl_now() -> u64 {
+    // Safety: no preconditions, SDL starts the clock itself if it isn't initialised
+    unsafe { sdl3::sys::timer::SDL_GetTicksNS() }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointerSample {
+    pub position: Vector2<f32>, // logical units
+    pub timestamp: u64,         // nanoseconds, SDL's event clock
+}
+
+// everything scrolled since the last tick, SDL doesn't say where a wheel event came from so
+// whole steps count as a wheel and anything fractional as a trackpad
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrollDelta {
+    pub precise: Vector2<f32>, // trackpads and smooth wheels, in SDL's scroll units
+    pub discrete: Vector2<f32>, // wheel notches
+}
+impl ScrollDelta {
+    pub fn zero() -> Self {
+        Self {
+            precise: Vector2::new(0.0, 0.0),
+            discrete: Vector2::new(0.0, 0.0),
+        }
+    }
+
+    pub fn total(&self) -> Vector2<f32> {
+        self.precise + self.discrete
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.total() == Vector2::new(0.0, 0.0)
+    }
 }
-#[derive(Debug, Clone, Copy)]
-pub enum ButtonState {
-    Up(i32),
-    Down(i32),
-} // up/down simply means current state, pressed/released now means keystate was also changed that frame
 
 #[derive(Debug, Clone, Copy, SmartDefault)]
 pub struct PenState {
@@ -29,6 +141,7 @@ pub struct PenState {
     pub tangential_pressure: f32, // 0.0 ~ 1.0, barrel pressure
     pub proximity: bool,
     pub is_down: bool,
+    pub eraser: bool, // the pen is flipped over, only known while it is down
 }
 
 type Callback = Option<Box<dyn FnMut() + Send + Sync + 'static>>;
@@ -36,40 +149,238 @@ type Callback = Option<Box<dyn FnMut() + Send + Sync + 'static>>;
 slotmap::new_key_type! { pub struct KeybindKey; }
 pub struct Keybind {
     pub button_groups: Vec<HashSet<Button>>,
+    pub prefix: Vec<Vec<HashSet<Button>>>, // chords to press first for sequences, e.g. Ctrl+K in Ctrl+K Ctrl+S
     pub exclusive_buttons: HashSet<Button>,
     pub attatched_screen: Option<ScreenKey>,
 
     pub state: ButtonState,
-    pub callbacks: (Callback, Callback, Callback),
+    pub callbacks: (Callback, Callback, Callback), // pressed, held, released
+}
+impl Keybind {
+    // groups are pressed in order, any button of a group counts, e.g. [[LCtrl, RCtrl], [S]]
+    pub fn new(button_groups: Vec<Vec<Button>>) -> Self {
+        Self {
+            button_groups: button_groups
+                .into_iter()
+                .map(|group| group.into_iter().collect())
+                .collect(),
+            prefix: vec![],
+            exclusive_buttons: HashSet::new(),
+            attatched_screen: None,
+            state: ButtonState::idle(),
+            callbacks: (None, None, None),
+        }
+    }
+
+    pub fn single(button: Button) -> Self {
+        Self::new(vec![vec![button]])
+    }
+
+    // one chord per step, each pressed within ActionMap::sequence_timeout of the previous
+    pub fn sequence(mut steps: Vec<Vec<Vec<Button>>>) -> Self {
+        let last = steps.pop().unwrap_or_default();
+        let mut keybind = Self::new(last);
+        keybind.prefix = steps
+            .into_iter()
+            .map(|step| Self::new(step).button_groups)
+            .collect();
+        keybind
+    }
+
+    // every chord of the keybind in order, the prefix followed by button_groups
+    pub fn steps(&self) -> impl Iterator<Item = &Vec<HashSet<Button>>> {
+        self.prefix
+            .iter()
+            .chain(std::iter::once(&self.button_groups))
+    }
+
+    // the keybind is inactive while any of these are held, e.g. Shift for a plain Ctrl+S
+    pub fn with_exclusive(mut self, buttons: Vec<Button>) -> Self {
+        self.exclusive_buttons.extend(buttons);
+        self
+    }
+
+    // only active while this screen is the active one
+    pub fn with_screen(mut self, screen: ScreenKey) -> Self {
+        self.attatched_screen = Some(screen);
+        self
+    }
+
+    pub fn on_pressed(mut self, callback: impl FnMut() + Send + Sync + 'static) -> Self {
+        self.callbacks.0 = Some(Box::new(callback));
+        self
+    }
+
+    pub fn on_held(mut self, callback: impl FnMut() + Send + Sync + 'static) -> Self {
+        self.callbacks.1 = Some(Box::new(callback));
+        self
+    }
+
+    pub fn on_released(mut self, callback: impl FnMut() + Send + Sync + 'static) -> Self {
+        self.callbacks.2 = Some(Box::new(callback));
+        self
+    }
+
+    pub fn is_satisfied(
+        &self,
+        button_states: &HashMap<Button, ButtonState>,
+        screens: &ScreenManager,
+    ) -> bool {
+        self.is_on_screen(screens) && self.chord_satisfied(&self.button_groups, button_states)
+    }
+
+    pub fn is_on_screen(&self, screens: &ScreenManager) -> bool {
+        match (&self.attatched_screen, screens.active_screen) {
+            (None, _) => true,
+            (Some(filter), Some(current)) => *filter == current,
+            _ => false,
+        }
+    }
+
+    // satisfied, and only just, so holding a chord doesn't count as pressing it again
+    pub fn chord_pressed(
+        &self,
+        chord: &[HashSet<Button>],
+        button_states: &HashMap<Button, ButtonState>,
+    ) -> bool {
+        let just_pressed = chord.last().is_some_and(|group| {
+            group.iter().any(|btn| {
+                button_states
+                    .get(btn)
+                    .is_some_and(|state| state.is_pressed())
+            })
+        });
+        just_pressed && self.chord_satisfied(chord, button_states)
+    }
+
+    pub fn chord_satisfied(
+        &self,
+        chord: &[HashSet<Button>],
+        button_states: &HashMap<Button, ButtonState>,
+    ) -> bool {
+        if chord.is_empty() {
+            return false;
+        }
+
+        // exclusive buttons
+        for button in &self.exclusive_buttons {
+            if button_states.get(button).is_some_and(|state| state.is_down) {
+                return false;
+            }
+        }
+
+        for i in 0..chord.len() {
+            let group = &chord[i];
+
+            // .filter_map(): .map() but with filtering
+            let latest_press = group
+                .iter()
+                .filter_map(|btn| button_states.get(btn).filter(|state| state.is_down))
+                .map(|state| state.changed_at)
+                .max();
+
+            if latest_press.is_none() {
+                return false;
+            }
+
+            // modifiers can go down in any order, as long as it's before the key they modify
+            let is_modifiers = |group: &HashSet<Button>| group.iter().all(Button::is_modifier);
+            let next = if is_modifiers(group) {
+                (i + 1..chord.len()).find(|&j| !is_modifiers(&chord[j]))
+            } else {
+                Some(i + 1).filter(|&j| j < chord.len())
+            };
+            if let Some(next) = next {
+                let next_group = &chord[next];
+                let earliest_next_press = next_group
+                    .iter()
+                    .filter_map(|btn| button_states.get(btn).filter(|state| state.is_down))
+                    .map(|state| state.changed_at)
+                    .min();
+
+                // .map_or()/.map_or_else(): unwraps option with a default value, default value is first
+                if earliest_next_press.is_some_and(|earliest| latest_press.unwrap() > earliest) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    // advances the state and fires the callback matching the transition
+    pub fn update(&mut self, active: bool, now: u64) {
+        if active == self.state.is_down {
+            self.state.tick();
+        } else {
+            self.state.set(active, now);
+        }
+
+        let callback = match (self.state.is_down, self.state.ticks) {
+            (true, 0) => &mut self.callbacks.0,
+            (true, _) => &mut self.callbacks.1,
+            (false, 0) => &mut self.callbacks.2,
+            (false, _) => return,
+        };
+        if let Some(callback) = callback {
+            callback();
+        }
+    }
 }
-impl Keybind {}
 
 pub struct InputManager {
-    pub keybinds: SlotMap<KeybindKey, Keybind>,
+    pub actions: ActionMap,
     pub button_states: HashMap<Button, ButtonState>,
-    pub mouse_pos_history: VecDeque<(f32, f32)>,
-    pub scroll: (f32, f32),
+    pub mouse_pos_history: VecDeque<PointerSample>, // newest first
+    pub velocity_window: Duration,                  // how far back mouse_velocity looks
+    pub scroll: ScrollDelta,
     pub pen: PenState,
+    pub strokes: StrokeRecorder,
+    pub drops: DropManager,
+    pub touch: TouchManager,
+    pub pointer: PointerManager,
+    pub text: TextInputManager,
+    pub modifiers: Mod, // including CapsLock and NumLock, left and right are separate bits
+    pub logical_scale: f32, // window coordinates -> logical units, kept in sync by SQWindow
+    pub axis_threshold: f32, // 0.0 ~ 1.0
     physical_left_button_down: bool,
+    gamepad_holders: HashMap<Button, HashSet<u32>>, // which devices hold each gamepad button
 }
 impl InputManager {
     pub fn new() -> Self {
         Self {
-            keybinds: SlotMap::with_key(),
+            actions: ActionMap::new(),
             button_states: HashMap::new(),
             mouse_pos_history: VecDeque::new(),
-            scroll: (0.0, 0.0),
+            velocity_window: Duration::from_millis(100),
+            scroll: ScrollDelta::zero(),
             pen: PenState::default(),
+            strokes: StrokeRecorder::new(),
+            drops: DropManager::new(),
+            touch: TouchManager::new(),
+            pointer: PointerManager::new(),
+            text: TextInputManager::new(),
+            modifiers: Mod::NOMOD,
+            logical_scale: 1.0,
+            axis_threshold: 0.5,
             physical_left_button_down: false,
+            gamepad_holders: HashMap::new(),
         }
     }
 
     pub fn reset_states(&mut self) {
         self.button_states.clear();
         self.mouse_pos_history.clear();
-        self.scroll = (0.0, 0.0);
+        self.scroll = ScrollDelta::zero();
         self.pen = PenState::default();
+        self.strokes.reset();
+        self.touch.reset();
+        self.pointer.reset();
+        self.text.reset();
+        // lock keys stay on while the window is unfocused
+        // Safety: only reads SDL's keyboard state, valid once the video subsystem is up
+        self.modifiers = Mod::from_bits_truncate(unsafe { sdl3::sys::keyboard::SDL_GetModState() });
         self.physical_left_button_down = false;
+        self.gamepad_holders.clear();
     }
 
     pub fn handle_event(&mut self, event: &Event) {
@@ -83,16 +394,32 @@ impl InputManager {
             | Event::PenButtonDown { .. }
             | Event::PenButtonUp { .. } => self.handle_button(event),
 
-            Event::MouseMotion { x, y, .. } => {
-                self.mouse_pos_history.push_front((*x, *y));
-                if self.mouse_pos_history.len() > 10 {
+            Event::ControllerButtonDown { .. }
+            | Event::ControllerButtonUp { .. }
+            | Event::ControllerAxisMotion { .. }
+            | Event::ControllerDeviceRemoved { .. } => self.handle_gamepad(event),
+
+            Event::MouseMotion {
+                timestamp, x, y, ..
+            } => {
+                self.mouse_pos_history.push_front(PointerSample {
+                    position: Vector2::new(*x, *y) * self.logical_scale,
+                    timestamp: *timestamp,
+                });
+                if self.mouse_pos_history.len() > 32 {
                     self.mouse_pos_history.pop_back();
                 }
             }
+            // several wheel events can arrive in one frame, none of them may be lost
             Event::MouseWheel { x, y, .. } => {
-                self.scroll = (*x, *y);
+                let delta = Vector2::new(*x, *y);
+                if x.fract() == 0.0 && y.fract() == 0.0 {
+                    self.scroll.discrete += delta;
+                } else {
+                    self.scroll.precise += delta;
+                }
             }
-            Event::PenMotion { x, y, .. } => {}
+            Event::PenMotion { .. } => {}
             Event::PenAxis { axis, value, .. } => match axis {
                 PenAxis::Pressure => self.pen.pressure = *value,
                 PenAxis::XTilt => self.pen.tilt.x = *value,
@@ -103,16 +430,45 @@ impl InputManager {
                 PenAxis::TangentialPressure => self.pen.tangential_pressure = *value,
                 PenAxis::Unknown => {}
                 PenAxis::Count => {}
-                _ => todo!(),
+                _ => {}
             },
+            Event::DropBegin { .. }
+            | Event::DropFile { .. }
+            | Event::DropText { .. }
+            | Event::DropComplete { .. } => self.drops.handle_event(event),
+            Event::TextInput { .. } | Event::TextEditing { .. } => self.text.handle_event(event),
             Event::PenProximityIn { .. } => self.pen.proximity = true,
             Event::PenProximityOut { .. } => self.pen.proximity = false,
             _ => {}
         }
+
+        self.pointer.handle_event(event, self.logical_scale);
+        // sampled after the pen state is updated so every sample carries the latest axes
+        self.strokes
+            .handle_event(event, &self.pen, self.logical_scale);
     }
 
     fn handle_button(&mut self, event: &Event) {
-        let (button, mut is_pressed) = match event {
+        // every key is tracked both by keycode and by scancode
+        if let Event::KeyDown {
+            scancode, keymod, ..
+        }
+        | Event::KeyUp {
+            scancode, keymod, ..
+        } = event
+        {
+            self.modifiers = *keymod;
+            if let Some(scancode) = scancode {
+                let is_pressed = matches!(event, Event::KeyDown { .. });
+                self.set_button(
+                    Button::Scancode(*scancode),
+                    is_pressed,
+                    event.get_timestamp(),
+                );
+            }
+        }
+
+        let (button, is_pressed) = match event {
             Event::KeyDown {
                 keycode: Some(key), ..
             } => (Button::Key(*key), true),
@@ -135,8 +491,9 @@ impl InputManager {
             }
             Event::MouseButtonDown { mouse_btn, .. } => (Button::Mouse(*mouse_btn), true),
             Event::MouseButtonUp { mouse_btn, .. } => (Button::Mouse(*mouse_btn), false),
-            Event::PenDown { .. } => {
+            Event::PenDown { eraser, .. } => {
                 self.pen.is_down = true;
+                self.pen.eraser = *eraser;
                 (Button::Mouse(MouseButton::Left), true)
             }
             Event::PenUp { .. } => {
@@ -154,115 +511,184 @@ impl InputManager {
             _ => return,
         };
 
-        if (button == Button::Mouse(MouseButton::Left)) {
-            is_pressed = is_pressed && self.pen.is_down;
+        self.set_button(button, is_pressed, event.get_timestamp());
+    }
+
+    // several gamepads can hold the same button, it is only released once all of them let go
+    fn handle_gamepad(&mut self, event: &Event) {
+        let changes = match event {
+            Event::ControllerButtonDown { which, button, .. } => {
+                vec![(*which, Button::Gamepad(*button), true)]
+            }
+            Event::ControllerButtonUp { which, button, .. } => {
+                vec![(*which, Button::Gamepad(*button), false)]
+            }
+            Event::ControllerAxisMotion {
+                which, axis, value, ..
+            } => {
+                let value = normalize_axis(*value);
+                vec![
+                    (
+                        *which,
+                        Button::GamepadAxis(*axis, AxisDirection::Positive),
+                        value > self.axis_threshold,
+                    ),
+                    (
+                        *which,
+                        Button::GamepadAxis(*axis, AxisDirection::Negative),
+                        value < -self.axis_threshold,
+                    ),
+                ]
+            }
+            Event::ControllerDeviceRemoved { which, .. } => self
+                .gamepad_holders
+                .iter()
+                .filter(|(_, holders)| holders.contains(which))
+                .map(|(button, _)| (*which, *button, false))
+                .collect(),
+            _ => return,
+        };
+
+        for (device, button, is_pressed) in changes {
+            let holders = self.gamepad_holders.entry(button).or_default();
+            let was_held = !holders.is_empty();
+            if is_pressed {
+                holders.insert(device);
+            } else {
+                holders.remove(&device);
+            }
+
+            let is_held = !holders.is_empty();
+            if is_held != was_held {
+                self.set_button(button, is_held, event.get_timestamp());
+            }
         }
+    }
 
-        let new_state = match self.button_states.entry(button) {
-            Entry::Occupied(mut entry) => match entry.get() {
-                ButtonState::Down(frames) if is_pressed => ButtonState::Down(frames + 1),
-                ButtonState::Down(_) => ButtonState::Up(0),
-                ButtonState::Up(frames) if !is_pressed => ButtonState::Up(frames + 1),
-                ButtonState::Up(_) => ButtonState::Down(0),
-            },
+    fn set_button(&mut self, button: Button, is_pressed: bool, timestamp: u64) {
+        match self.button_states.entry(button) {
+            // repeats leave the counter alone, it is only advanced by tick()
+            Entry::Occupied(mut entry) => entry.get_mut().set(is_pressed, timestamp),
             Entry::Vacant(entry) => {
-                if is_pressed {
-                    ButtonState::Down(0)
-                } else {
-                    ButtonState::Up(0)
-                }
+                entry.insert(ButtonState::new(is_pressed, timestamp));
             }
-        };
+        }
+    }
 
-        // HashMap.insert updates kvp or inserts them when non-existent, while returning old states
-        self.button_states.insert(button, new_state);
+    // advances every button's tick counter, called once per update tick
+    pub fn tick(&mut self) {
+        for state in self.button_states.values_mut() {
+            state.tick();
+        }
+        self.strokes.tick();
+        self.pointer.tick();
+        self.scroll = ScrollDelta::zero();
+    }
+
+    // None for buttons that haven't been touched since the window got focus
+    pub fn state(&self, button: &Button) -> Option<&ButtonState> {
+        self.button_states.get(button)
     }
 
     pub fn is_down(&self, button: &Button) -> bool {
-        matches!(self.button_states.get(button), Some(ButtonState::Down(_)))
+        self.state(button).is_some_and(|state| state.is_down)
     }
 
-    pub fn frames(&self, button: &Button) -> i32 {
-        match self.button_states.get(button) {
-            Some(ButtonState::Down(f)) | Some(ButtonState::Up(f)) => *f,
-            _ => i32::max_value(),
-        }
+    // update ticks since the button was last pressed or released
+    pub fn ticks(&self, button: &Button) -> Option<u32> {
+        self.state(button).map(|state| state.ticks)
     }
 
     pub fn is_pressed(&self, button: &Button) -> bool {
-        match self.button_states.get(button) {
-            Some(ButtonState::Down(frames)) => *frames == 0,
-            _ => false,
-        }
+        self.state(button).is_some_and(|state| state.is_pressed())
     }
 
     pub fn is_released(&self, button: &Button) -> bool {
-        match self.button_states.get(button) {
-            Some(ButtonState::Up(frames)) => *frames == 0,
-            _ => false,
-        }
+        self.state(button).is_some_and(|state| state.is_released())
     }
 
-    pub fn update(&mut self, screens: &ScreenManager) {
-        for (_, mut keybind) in &mut self.keybinds {
-            let screen_ok = match (&keybind.attatched_screen, screens.active_screen) {
-                (None, _) => true,
-                // (Some(filter), Some(current)) => filter == *current,
-                _ => false,
-            };
+    pub fn held_for(&self, button: &Button) -> Option<Duration> {
+        self.state(button)?.held_for(sdl_now())
+    }
 
-            let mut active = screen_ok;
+    // held continuously for at least threshold
+    pub fn is_long_pressed(&self, button: &Button, threshold: Duration) -> bool {
+        self.held_for(button)
+            .is_some_and(|held_for| held_for >= threshold)
+    }
 
-            // exclusive buttons
-            for button in &keybind.exclusive_buttons {
-                if matches!(self.button_states.get(button), Some(ButtonState::Down(_))) {
-                    active = false;
-                    break;
-                }
-            }
+    pub fn is_double_pressed(&self, button: &Button, interval: Duration) -> bool {
+        self.state(button)
+            .is_some_and(|state| state.is_double_press(interval))
+    }
 
-            for i in 0..keybind.button_groups.len() {
-                let group = &keybind.button_groups[i];
+    // logical, None until the mouse has moved over the window
+    pub fn mouse_position(&self) -> Option<Vector2<f32>> {
+        self.mouse_pos_history.front().map(|sample| sample.position)
+    }
 
-                // .filter_map(): .map() but with filtering
-                let min_dur = group
-                    .iter()
-                    .filter_map(|btn| match self.button_states.get(btn) {
-                        Some(ButtonState::Down(frames)) => Some(*frames),
-                        _ => None,
-                    })
-                    .min();
+    // logical units per second, a least squares fit over the last velocity_window so one
+    // jittery event doesn't dominate, zero once the mouse has been still for that long
+    pub fn mouse_velocity(&self) -> Vector2<f32> {
+        let now = sdl_now();
+        let window = self.velocity_window.as_nanos() as u64;
+        let samples: Vec<_> = self
+            .mouse_pos_history
+            .iter()
+            .take_while(|sample| now.saturating_sub(sample.timestamp) <= window)
+            .map(|sample| {
+                let age = now.saturating_sub(sample.timestamp) as f32 / 1e9;
+                (-age, sample.position)
+            })
+            .collect();
+        if samples.len() < 2 {
+            return Vector2::new(0.0, 0.0);
+        }
 
-                if min_dur.is_none() {
-                    active = false;
-                    break;
-                }
+        let count = samples.len() as f32;
+        let mean_time = samples.iter().map(|(time, _)| time).sum::<f32>() / count;
+        let mean_position = samples
+            .iter()
+            .fold(Vector2::new(0.0, 0.0), |sum, (_, position)| sum + position)
+            / count;
+        let (mut covariance, mut variance) = (Vector2::new(0.0, 0.0), 0.0);
+        for (time, position) in &samples {
+            covariance += (position - mean_position) * (time - mean_time);
+            variance += (time - mean_time) * (time - mean_time);
+        }
+        if variance <= f32::EPSILON {
+            return Vector2::new(0.0, 0.0);
+        }
+        covariance / variance
+    }
 
-                // Compare with next group if exists
-                if i + 1 < keybind.button_groups.len() {
-                    let next_group = &keybind.button_groups[i + 1];
-                    let max_next_dur = next_group
-                        .iter()
-                        .filter_map(|btn| match self.button_states.get(btn) {
-                            Some(ButtonState::Down(frames)) => Some(*frames),
-                            _ => None,
-                        })
-                        .max();
-
-                    // .map_or()/.map_or_else(): unwraps option with a default value, default value is first
-                    if max_next_dur.is_some_and(|max| min_dur.unwrap() < max) {
-                        active = false;
-                        break;
-                    }
-                }
-            }
+    pub fn ctrl(&self) -> bool {
+        self.modifiers.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD)
+    }
 
-            keybind.state = match (active, keybind.state) {
-                (true, ButtonState::Down(frames)) => ButtonState::Down(frames + 1),
-                (true, ButtonState::Up(_)) => ButtonState::Down(0),
-                (false, ButtonState::Up(frames)) => ButtonState::Up(frames + 1),
-                (false, ButtonState::Down(_)) => ButtonState::Up(0),
-            };
-        }
+    pub fn shift(&self) -> bool {
+        self.modifiers.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD)
+    }
+
+    pub fn alt(&self) -> bool {
+        self.modifiers.intersects(Mod::LALTMOD | Mod::RALTMOD)
+    }
+
+    pub fn gui(&self) -> bool {
+        self.modifiers.intersects(Mod::LGUIMOD | Mod::RGUIMOD)
+    }
+
+    pub fn caps_lock(&self) -> bool {
+        self.modifiers.contains(Mod::CAPSMOD)
+    }
+
+    pub fn num_lock(&self) -> bool {
+        self.modifiers.contains(Mod::NUMMOD)
+    }
+
+    // evaluates every keybind against this tick's button states and fires their callbacks
+    pub fn update(&mut self, screens: &ScreenManager) {
+        self.actions.update(&self.button_states, screens);
+        self.pointer.update(self.mouse_position());
     }
 }
@@ -0,0 +1,386 @@
+use crate::*;
+use std::{collections::HashSet, path::Path};
+
+use anyhow::anyhow;
+use sdl3::{
+    keyboard::{Keycode, Scancode},
+    mouse::MouseButton,
+    sys::keycode::SDL_KMOD_NONE,
+};
+
+// written as one name but matching either side of the keyboard
+pub const MODIFIERS: [(&str, [Keycode; 2]); 4] = [
+    ("Ctrl", [Keycode::LCtrl, Keycode::RCtrl]),
+    ("Shift", [Keycode::LShift, Keycode::RShift]),
+    ("Alt", [Keycode::LAlt, Keycode::RAlt]),
+    ("Super", [Keycode::LGui, Keycode::RGui]),
+];
+
+fn modifier_group(keys: &[Keycode; 2]) -> HashSet<Button> {
+    keys.iter().map(|key| Button::Key(*key)).collect()
+}
+
+// the characters that separate names in a keybind, e.g. "Keypad +" is written as Keypad_\+
+const SEPARATORS: [char; 4] = ['+', '|', ',', '\\'];
+
+fn escape(name: &str) -> String {
+    let mut escaped = String::new();
+    for ch in name.chars() {
+        if SEPARATORS.contains(&ch) {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+fn unescape(name: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = name.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => unescaped.extend(chars.next()),
+            _ => unescaped.push(ch),
+        }
+    }
+    unescaped
+}
+
+// the parts between separators that aren't escaped, still escaped themselves
+fn split_unescaped(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, ch) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else if ch == separator {
+            parts.push(&text[start..i]);
+            start = i + ch.len_utf8();
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+impl Button {
+    // spaces in SDL's key names become underscores so a name is always one word, and separators
+    // are escaped with a backslash
+    pub fn name(&self) -> String {
+        match self {
+            Button::Key(key) => escape(&key.name().replace(' ', "_")),
+            Button::Scancode(scancode) => {
+                format!("Scancode:{}", escape(&scancode.name().replace(' ', "_")))
+            }
+            Button::Mouse(button) => format!("Mouse{:?}", button),
+            Button::Pen(button) => format!("Pen{}", button),
+            Button::Gamepad(button) => format!("Gamepad:{}", button.string()),
+            Button::GamepadAxis(axis, direction) => {
+                format!("GamepadAxis:{}:{:?}", axis.string(), direction)
+            }
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Button> {
+        let name = &unescape(name);
+        if let Some(rest) = name.strip_prefix("Scancode:") {
+            return Scancode::from_name(rest)
+                .or_else(|| Scancode::from_name(&rest.replace('_', " ")))
+                .map(Button::Scancode);
+        }
+        if let Some(rest) = name.strip_prefix("GamepadAxis:") {
+            let (axis, direction) = rest.split_once(':')?;
+            let direction = match direction {
+                "Positive" => AxisDirection::Positive,
+                "Negative" => AxisDirection::Negative,
+                _ => return None,
+            };
+            return GamepadAxis::from_string(axis).map(|axis| Button::GamepadAxis(axis, direction));
+        }
+        if let Some(rest) = name.strip_prefix("Gamepad:") {
+            return GamepadButton::from_string(rest).map(Button::Gamepad);
+        }
+        if let Some(rest) = name.strip_prefix("Mouse") {
+            return match rest {
+                "Left" => Some(Button::Mouse(MouseButton::Left)),
+                "Middle" => Some(Button::Mouse(MouseButton::Middle)),
+                "Right" => Some(Button::Mouse(MouseButton::Right)),
+                "X1" => Some(Button::Mouse(MouseButton::X1)),
+                "X2" => Some(Button::Mouse(MouseButton::X2)),
+                _ => None,
+            };
+        }
+        if let Some(button) = name.strip_prefix("Pen").and_then(|rest| rest.parse().ok()) {
+            return Some(Button::Pen(button));
+        }
+
+        // the underscore key is called "_" itself
+        Keycode::from_name(name)
+            .or_else(|| Keycode::from_name(&name.replace('_', " ")))
+            .map(Button::Key)
+    }
+
+    // what to show the user, scancodes are named after what they type on the current layout
+    pub fn display_name(&self) -> String {
+        match self {
+            Button::Key(key) => key.name(),
+            Button::Scancode(scancode) => match self.twin() {
+                Some(Button::Key(key)) if !key.name().is_empty() => key.name(),
+                _ => scancode.name().to_string(),
+            },
+            Button::Mouse(button) => format!("Mouse {:?}", button),
+            Button::Pen(button) => format!("Pen {}", button),
+            Button::Gamepad(button) => button.string(),
+            Button::GamepadAxis(axis, AxisDirection::Positive) => format!("{}+", axis.string()),
+            Button::GamepadAxis(axis, AxisDirection::Negative) => format!("{}-", axis.string()),
+        }
+    }
+
+    // the same physical key under the current layout, Key for a Scancode and the other way round
+    pub fn twin(&self) -> Option<Button> {
+        match self {
+            Button::Key(key) => {
+                Scancode::from_keycode(*key, std::ptr::null_mut()).map(Button::Scancode)
+            }
+            Button::Scancode(scancode) => {
+                Keycode::from_scancode(*scancode, SDL_KMOD_NONE, false).map(Button::Key)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_modifier(&self) -> bool {
+        let key = match (*self, self.twin()) {
+            (Button::Key(key), _) | (Button::Scancode(_), Some(Button::Key(key))) => key,
+            _ => return false,
+        };
+        MODIFIERS.iter().any(|(_, keys)| keys.contains(&key))
+    }
+}
+
+fn chord_to_string(chord: &[HashSet<Button>], name: fn(&Button) -> String) -> String {
+    chord
+        .iter()
+        .map(|group| {
+            match MODIFIERS
+                .iter()
+                .find(|(_, keys)| *group == modifier_group(keys))
+            {
+                Some((name, _)) => name.to_string(),
+                None => {
+                    let mut names: Vec<String> = group.iter().map(name).collect();
+                    names.sort();
+                    names.join("|")
+                }
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+fn parse_chord(text: &str) -> anyhow::Result<Vec<HashSet<Button>>> {
+    let text = text.trim();
+    let mut tokens = split_unescaped(text, '+');
+    // the plus key written without its backslash, "Ctrl++" splits into two empty tokens at the end
+    if text == "+" || (text.ends_with("++") && !text.ends_with("\\++")) {
+        tokens.truncate(tokens.len() - 2);
+        tokens.push("+");
+    }
+
+    tokens
+        .into_iter()
+        .map(|token| {
+            let token = token.trim();
+            if let Some((_, keys)) = MODIFIERS
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(token))
+            {
+                return Ok(modifier_group(keys));
+            }
+            split_unescaped(token, '|')
+                .into_iter()
+                .map(|name| Button::from_name(name).ok_or(anyhow!("Unknown button \"{}\"", name)))
+                .collect()
+        })
+        .collect()
+}
+
+impl Keybind {
+    // e.g. "Ctrl+Shift+P", or "Ctrl+K, Ctrl+S" for a sequence
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut steps = split_unescaped(text, ',')
+            .into_iter()
+            .map(parse_chord)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let last = steps.pop().filter(|chord| !chord.is_empty());
+        let Some(last) = last else {
+            return Err(anyhow!("Empty keybind"));
+        };
+
+        let mut keybind = Keybind::new(vec![]);
+        keybind.button_groups = last;
+        keybind.prefix = steps;
+        Ok(keybind.with_modifier_exclusives())
+    }
+
+    // the modifiers the keybind doesn't use become exclusive, so Ctrl+S ignores Ctrl+Shift+S
+    pub fn with_modifier_exclusives(mut self) -> Self {
+        for (_, keys) in MODIFIERS {
+            let group = modifier_group(&keys);
+            let used = self
+                .button_groups
+                .iter()
+                .flatten()
+                .any(|b| group.contains(b));
+            if !used {
+                self.exclusive_buttons.extend(group);
+            }
+        }
+        self
+    }
+
+    // exclusive buttons aren't written, parse derives them from the modifiers again
+    pub fn to_keymap_string(&self) -> String {
+        self.steps()
+            .map(|chord| chord_to_string(chord, Button::name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    // e.g. "Ctrl+Z" for Ctrl+Scancode:W on AZERTY, for menus and tooltips
+    pub fn display_name(&self) -> String {
+        self.steps()
+            .map(|chord| chord_to_string(chord, Button::display_name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl ActionMap {
+    // one "action = keybind" per line, actions with several keybinds get several lines
+    pub fn to_keymap(&self) -> String {
+        let mut actions: Vec<_> = self.actions.iter().collect();
+        actions.sort_by_key(|(action, _)| action.as_str());
+
+        let mut keymap = String::new();
+        for (action, keys) in actions {
+            for keybind in keys.iter().filter_map(|key| self.keybinds.get(*key)) {
+                keymap += &format!("{} = {}\n", action, keybind.to_keymap_string());
+            }
+        }
+        keymap
+    }
+
+    // replaces the keybinds of every action in the keymap, actions it doesn't mention are kept,
+    // nothing changes if any line fails to parse
+    pub fn load_keymap(&mut self, keymap: &str) -> anyhow::Result<()> {
+        let mut parsed: Vec<(String, Vec<Keybind>)> = vec![];
+        for (i, line) in keymap.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (action, keybind) = line
+                .split_once(" = ")
+                .ok_or(anyhow!("Line {}: expected \"action = keybind\"", i + 1))?;
+            let keybind = Keybind::parse(keybind).map_err(|e| anyhow!("Line {}: {}", i + 1, e))?;
+
+            let action = action.trim();
+            match parsed.iter_mut().find(|(name, _)| name == action) {
+                Some((_, keybinds)) => keybinds.push(keybind),
+                None => parsed.push((action.to_string(), vec![keybind])),
+            }
+        }
+
+        for (action, keybinds) in parsed {
+            self.rebind(&action, keybinds);
+        }
+        Ok(())
+    }
+
+    pub fn save_keymap_file(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        std::fs::write(path, self.to_keymap())?;
+        Ok(())
+    }
+
+    pub fn load_keymap_file(&mut self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let keymap = std::fs::read_to_string(path)?;
+        self.load_keymap(&keymap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sdl3::sys::keycode::SDLK_SCANCODE_MASK;
+
+    // printable keys, keys named after their scancode, and SDL's extended keys
+    fn every_keycode() -> impl Iterator<Item = Keycode> {
+        (0..0x80)
+            .chain((0..512).map(|scancode| scancode | SDLK_SCANCODE_MASK as i32))
+            .chain(0x2000_0000..0x2000_0020)
+            .filter_map(Keycode::from_i32)
+            .filter(|key| !matches!(key, Keycode::Unknown | Keycode::ScancodeMask))
+            .filter(|key| !key.name().is_empty())
+    }
+
+    #[test]
+    fn keymap_round_trips_every_keycode() {
+        let mut actions = ActionMap::new();
+        for key in every_keycode() {
+            let keybind = Keybind::parse(&format!("Ctrl+{}", Button::Key(key).name())).unwrap();
+            actions.bind(format!("{:?}", key), keybind);
+        }
+        let keymap = actions.to_keymap();
+
+        let mut loaded = ActionMap::new();
+        loaded.load_keymap(&keymap).unwrap();
+        assert_eq!(loaded.to_keymap(), keymap);
+        for key in every_keycode() {
+            let keybind = &loaded.keybinds[loaded.actions[&format!("{:?}", key)][0]];
+            assert!(
+                keybind.button_groups[1].contains(&Button::Key(key)),
+                "{:?}",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn separators_in_names() {
+        for name in ["Keypad_\\+", "Keypad_\\|\\|", "\\,", "\\\\"] {
+            let keybind = Keybind::parse(&format!("Ctrl+{}, Shift+{}", name, name)).unwrap();
+            assert_eq!(keybind.prefix.len(), 1, "{}", name);
+            assert_eq!(keybind.button_groups.len(), 2, "{}", name);
+            assert_eq!(
+                keybind.to_keymap_string(),
+                format!("Ctrl+{}, Shift+{}", name, name)
+            );
+        }
+        // the plus key as people tend to write it
+        let keybind = Keybind::parse("Ctrl++").unwrap();
+        assert!(keybind.button_groups[1].contains(&Button::Key(Keycode::Plus)));
+    }
+
+    #[test]
+    fn modifiers_in_any_order() {
+        let keybind = Keybind::parse("Ctrl+Shift+P").unwrap();
+        let press = |order: [Keycode; 3]| {
+            let states: HashMap<Button, ButtonState> = order
+                .iter()
+                .enumerate()
+                .map(|(i, key)| (Button::Key(*key), ButtonState::new(true, i as u64)))
+                .collect();
+            keybind.chord_pressed(&keybind.button_groups, &states)
+        };
+        assert!(press([Keycode::LCtrl, Keycode::LShift, Keycode::P]));
+        assert!(press([Keycode::LShift, Keycode::LCtrl, Keycode::P]));
+        assert!(press([Keycode::RShift, Keycode::LCtrl, Keycode::P]));
+        // the key still has to come last
+        assert!(!press([Keycode::LCtrl, Keycode::P, Keycode::LShift]));
+        assert!(!press([Keycode::P, Keycode::LShift, Keycode::LCtrl]));
+    }
+}
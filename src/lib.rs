@@ -1,23 +1,65 @@
 #![allow(unused, unused_variables, dead_code)]
+pub mod action;
+pub mod app;
 pub mod assets;
 pub mod camera;
+pub mod chrome;
+pub mod clipboard;
 pub mod create_surface;
+pub mod drop;
+pub mod font;
+pub mod gamepad;
 pub mod input;
+pub mod keymap;
+pub mod paragraph;
+pub mod pointer;
+pub mod recording;
 pub mod render;
+pub mod rich_text;
 pub mod screen;
+pub mod scroll;
+pub mod sdf;
+pub mod shared;
+pub mod stroke;
 pub mod structs;
+pub mod text_input;
 pub mod texture;
+pub mod touch;
+pub mod window;
 
+pub use action::*;
+pub use app::*;
 pub use assets::*;
 pub use camera::*;
+pub use chrome::*;
+pub use clipboard::*;
 pub use create_surface::*;
+pub use drop::*;
+pub use font::*;
+pub use gamepad::*;
 pub use input::*;
+pub use keymap::*;
+pub use paragraph::*;
+pub use pointer::*;
+pub use recording::*;
 pub use render::*;
+pub use rich_text::*;
 pub use screen::*;
+pub use scroll::*;
+pub use sdf::*;
+pub use shared::*;
+pub use stroke::*;
 pub use structs::*;
+pub use text_input::*;
 pub use texture::*;
+pub use touch::*;
+pub use window::*;
 
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
 
 use anyhow::anyhow;
 use cgmath::Vector2;
@@ -30,14 +72,24 @@ pub struct SQ {
     pub render_context: Arc<RenderContext>,
     pub assets: Arc<AssetManager>,
     pub windows: HashMap<u32, SQWindow>,
+    pub shared: Shared,
     pub running: bool,
+    timestep: Timestep,
+    pub recorder: Option<InputRecorder>,
+    pub replay: Option<InputReplay>,
+    accumulator: f32,
 }
 impl SQ {
     pub async fn new() -> anyhow::Result<Self> {
+        // closing the last window goes through on_window_close_requested like any other
+        sdl3::hint::set("SDL_QUIT_ON_LAST_WINDOW_CLOSE", "0");
+
         let sdl_context = Arc::new(sdl3::init()?);
         let event_pump = sdl_context.event_pump()?;
         let render_context = Arc::new(RenderContext::new(sdl_context.clone()).await?);
         let assets = Arc::new(AssetManager::new());
+        let clipboard = Clipboard::new(&render_context.video_subsystem);
+        let gamepads = GamepadManager::new(&sdl_context)?;
 
         Ok(Self {
             sdl_context,
@@ -45,19 +97,27 @@ impl SQ {
             render_context,
             assets,
             windows: HashMap::new(),
+            shared: Shared::new(clipboard, gamepads),
             running: true,
+            timestep: Timestep::Variable,
+            recorder: None,
+            replay: None,
+            accumulator: 0.0,
         })
     }
 
-    pub async fn run(&mut self) -> anyhow::Result<()> {
+    pub async fn run(&mut self, app: &mut impl SQApp) -> anyhow::Result<()> {
+        let mut last_frame = Instant::now();
         'running: loop {
             if !self.running {
                 break 'running;
             }
-            let frame_start = std::time::Instant::now();
+            let frame_start = Instant::now();
+            let dt = (frame_start - last_frame).as_secs_f32();
+            last_frame = frame_start;
 
-            self.update();
-            self.render();
+            self.update(app, dt);
+            self.render(app, self.alpha());
 
             let frame_time = frame_start.elapsed();
             let target_frame_time = Duration::from_secs_f32(1.0 / 60.0);
@@ -74,27 +134,97 @@ impl SQ {
         width: u32,
         height: u32,
     ) -> anyhow::Result<u32> {
-        let window = SQWindow::new(
-            self.render_context.clone(),
-            self.assets.clone(),
-            title,
-            width,
-            height,
-        )?;
+        self.create_window_with(WindowBuilder::new(title, width, height))
+            .await
+    }
+
+    pub async fn create_window_with(&mut self, builder: WindowBuilder) -> anyhow::Result<u32> {
+        self.open_window(&builder)
+    }
+
+    fn open_window(&mut self, builder: &WindowBuilder) -> anyhow::Result<u32> {
+        let mut window = SQWindow::new(self.render_context.clone(), self.assets.clone(), builder)?;
+
+        if let Some(parent_id) = builder.parent {
+            let parent = self
+                .windows
+                .get(&parent_id)
+                .ok_or(anyhow!("Parent window {} not found", parent_id))?;
+            set_parent(&window.window, &parent.window)?;
+            window.parent = Some(parent_id);
+        }
 
         let window_id = window.window.id();
         self.windows.insert(window_id, window);
         Ok(window_id)
     }
 
+    // closes the window's children along with it
     pub fn close_window(&mut self, window_id: u32) -> anyhow::Result<()> {
         if let Some(window) = self.windows.remove(&window_id) {
+            let children: Vec<_> = self
+                .windows
+                .iter()
+                .filter(|(_, w)| w.parent == Some(window_id))
+                .map(|(id, _)| *id)
+                .collect();
+            for child in children {
+                self.close_window(child)?;
+            }
+
+            if self
+                .shared
+                .drag
+                .as_ref()
+                .is_some_and(|d| d.source == window_id)
+            {
+                self.shared.drag = None;
+            }
             Ok(())
         } else {
             Err(anyhow!("Failed to close window"))
         }
     }
 
+    // the window containing a point in desktop window coordinates, and the logical position in it,
+    // children stay above their parents so the deepest one wins, then the focused one
+    fn window_at(
+        windows: &HashMap<u32, SQWindow>,
+        screen_position: Vector2<f32>,
+    ) -> Option<(u32, Vector2<f32>)> {
+        let depth = |window: &SQWindow| {
+            let mut depth = 0;
+            let mut parent = window.parent;
+            while let Some(window) = parent.and_then(|id| windows.get(&id)) {
+                parent = window.parent;
+                depth += 1;
+            }
+            depth
+        };
+
+        windows
+            .iter()
+            .filter(|(_, window)| !window.minimized)
+            .filter_map(|(id, window)| {
+                let (x, y) = window.window.position();
+                let (width, height) = window.window.size();
+                let bounds = Rectangle {
+                    x: x as f32,
+                    y: y as f32,
+                    width: width as f32,
+                    height: height as f32,
+                };
+                bounds
+                    .contains(screen_position)
+                    .then_some((id, window, bounds))
+            })
+            .max_by_key(|(_, window, _)| (depth(window), window.focused))
+            .map(|(id, window, bounds)| {
+                let local = screen_position - bounds.min();
+                (*id, window.window_to_logical(local))
+            })
+    }
+
     fn get_window_id(event: &Event) -> Option<u32> {
         match event {
             Event::Window { window_id, .. }
@@ -123,42 +253,372 @@ impl SQ {
 
     fn get_window(window_id: u32) {}
 
-    pub fn update(&mut self) {
-        self.handle_events();
+    pub fn timestep(&self) -> Timestep {
+        self.timestep
+    }
+
+    pub fn set_timestep(&mut self, timestep: Timestep) -> anyhow::Result<()> {
+        if let Timestep::Fixed {
+            tick_rate,
+            max_steps,
+        } = timestep
+        {
+            if !(tick_rate > 0.0 && tick_rate.is_finite()) {
+                return Err(anyhow!("Tick rate must be positive, got {}", tick_rate));
+            }
+            if max_steps == 0 {
+                return Err(anyhow!("Max steps must be at least 1"));
+            }
+        }
+        self.timestep = timestep;
+        self.accumulator = 0.0;
+        Ok(())
+    }
+
+    pub fn update(&mut self, app: &mut impl SQApp, dt: f32) {
+        for builder in std::mem::take(&mut self.shared.spawn_queue) {
+            if let Err(error) = self.open_window(&builder) {
+                app.on_window_spawn_failed(&builder, error);
+            }
+        }
+
+        // windows created since the last frame get their on_start before anything else
+        for (_, window) in self.windows.iter_mut().filter(|(_, w)| !w.started) {
+            window.started = true;
+            let (mut ctx, _) = window.split(&self.render_context, &self.assets, &mut self.shared);
+            app.on_start(&mut ctx);
+        }
+
+        // a replay steps with the recorded timing instead of the clock's
+        let replayed = self.replay.as_ref().and_then(|replay| replay.frame());
+        let dt = replayed.map_or(dt, |frame| frame.dt);
+
+        self.handle_events(app);
+        self.deliver_gestures(app);
+
+        let ticks = match self.timestep {
+            Timestep::Variable => {
+                self.step(app, dt);
+                1
+            }
+            Timestep::Fixed {
+                tick_rate,
+                max_steps,
+            } => {
+                let tick = 1.0 / tick_rate;
+                self.accumulator += dt;
+
+                let mut steps = 0;
+                // a replay runs as many ticks as were recorded, whatever the accumulator says
+                let max_steps = replayed.map_or(max_steps, |frame| frame.ticks);
+                while steps < max_steps && (replayed.is_some() || self.accumulator >= tick) {
+                    self.step(app, tick);
+                    self.accumulator = (self.accumulator - tick).max(0.0);
+                    steps += 1;
+                }
+
+                // drop whatever we couldn't catch up on instead of spiralling after a stall
+                if self.accumulator >= tick {
+                    self.accumulator %= tick;
+                }
+                steps
+            }
+        };
+        if let Some(recorder) = &mut self.recorder {
+            recorder.end_frame(dt, ticks);
+        }
+
+        self.deliver_messages(app);
+    }
+
+    fn deliver_messages(&mut self, app: &mut impl SQApp) {
+        // messages sent from on_message are delivered in the same loop
+        while let Some(message) = self.shared.messages.pop_front() {
+            for (id, window) in self.windows.iter_mut() {
+                let recipient = match message.to {
+                    Some(to) => *id == to,
+                    None => *id != message.from,
+                };
+                if recipient {
+                    let (mut ctx, _) =
+                        window.split(&self.render_context, &self.assets, &mut self.shared);
+                    app.on_message(&mut ctx, message.from, message.payload.as_ref());
+                }
+            }
+        }
+    }
+
+    fn step(&mut self, app: &mut impl SQApp, dt: f32) {
+        for (_, window) in self.windows.iter_mut() {
+            window.inputs.update(&self.shared.screens);
+            let (mut ctx, _) = window.split(&self.render_context, &self.assets, &mut self.shared);
+            app.on_update(&mut ctx, dt);
+            window.inputs.tick();
+        }
+    }
+
+    // interpolation factor between the last fixed tick and the next
+    pub fn alpha(&self) -> f32 {
+        match self.timestep {
+            Timestep::Variable => 1.0,
+            Timestep::Fixed { tick_rate, .. } => self.accumulator * tick_rate,
+        }
+    }
 
-        // todo: game logic
+    // everything handle_events receives is kept until stop_recording
+    pub fn start_recording(&mut self) {
+        self.recorder = Some(InputRecorder::new());
+        // ticks only line up on replay if both start from an empty accumulator
+        self.accumulator = 0.0;
     }
 
-    fn handle_events(&mut self) {
-        let events: Vec<_> = self.event_pump.poll_iter().collect();
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        self.recorder.take().map(|recorder| recorder.recording)
+    }
+
+    // feeds a recording back in, one recorded frame per update, the real devices are ignored
+    // until it runs out, e.g. with SDL_VIDEO_DRIVER=dummy for tests on a headless machine
+    pub fn replay(&mut self, recording: InputRecording) {
+        self.replay = Some(InputReplay::new(recording));
+        self.accumulator = 0.0;
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    // sdl3 turns SDL_EVENT_DROP_POSITION into an Event::Unknown without its window or position,
+    // so events are polled raw and those are kept aside in the order they arrived
+    fn poll_events() -> (Vec<Event>, VecDeque<(u32, Vector2<f32>)>) {
+        let mut events = vec![];
+        let mut drop_positions = VecDeque::new();
+        let mut raw = std::mem::MaybeUninit::uninit();
+        // Safety: SDL fills in a whole event whenever it returns true, the event pump keeps the
+        // events subsystem alive, and type is valid for every event
+        while unsafe { sys::events::SDL_PollEvent(raw.as_mut_ptr()) } {
+            let raw = unsafe { raw.assume_init() };
+            if unsafe { raw.r#type } == sys::events::SDL_EVENT_DROP_POSITION.0 {
+                let drop = unsafe { raw.drop };
+                drop_positions.push_back((drop.windowID, Vector2::new(drop.x, drop.y)));
+            }
+            events.push(Event::from_ll(raw));
+        }
+        (events, drop_positions)
+    }
+
+    fn handle_events(&mut self, app: &mut impl SQApp) {
+        let (mut events, mut drop_positions) = SQ::poll_events();
+        if let Some(replay) = &mut self.replay {
+            events.retain(|event| !is_user_input(event));
+            events.extend(replay.next_frame());
+            if replay.is_finished() {
+                self.replay = None;
+            }
+        }
+        if let Some(recorder) = &mut self.recorder {
+            for event in &events {
+                recorder.record(event);
+            }
+        }
+
         for event in events {
+            if self.shared.drag.is_some() {
+                self.track_drag(&event, app);
+            }
+
             // window events
             if let Some(window_id) = SQ::get_window_id(&event) {
+                let mut close = false;
                 if let Some(window) = self.windows.get_mut(&window_id) {
-                    window.handle_event(&event.clone(), &self.render_context);
+                    let window_events = window.handle_event(&event, &self.render_context);
+                    let (mut ctx, _) =
+                        window.split(&self.render_context, &self.assets, &mut self.shared);
+                    app.on_event(&mut ctx, &event);
+
+                    if let Some((target, session)) = ctx.inputs.drops.take_completed() {
+                        app.on_drop(&mut ctx, target, session);
+                    }
+
+                    for window_event in window_events {
+                        match window_event {
+                            SQWindowEvent::CloseRequested => {
+                                close = app.on_window_close_requested(&mut ctx)
+                            }
+                            _ => app.on_window_event(&mut ctx, &window_event),
+                        }
+                    }
+                }
+
+                if close {
+                    self.close_window(window_id);
+                    if self.windows.is_empty() {
+                        self.running = false;
+                    }
                 }
             } else {
                 // global
                 match event {
                     Event::Quit { .. } => {
-                        self.running = false;
+                        if app.on_close_requested() {
+                            self.running = false;
+                        }
+                    }
+                    Event::ClipboardUpdate { .. } => {
+                        app.on_clipboard_changed(&self.shared.clipboard)
+                    }
+                    // a gamepad SDL can't open is left out, as if it was never connected
+                    Event::ControllerDeviceAdded { which, .. } => {
+                        let opened = self.shared.gamepads.handle_event(&event).is_ok();
+                        if opened {
+                            app.on_gamepad_added(&mut self.shared.gamepads, which);
+                        }
+                    }
+                    Event::ControllerDeviceRemoved { which, .. } => {
+                        let _ = self.shared.gamepads.handle_event(&event);
+                        // release whatever it was holding everywhere, not just in the focused window
+                        for window in self.windows.values_mut() {
+                            window.inputs.handle_event(&event);
+                        }
+                        app.on_gamepad_removed(&mut self.shared.gamepads, which);
+                    }
+                    // finger events carry no window id, they go to the focused window since
+                    // touching a window focuses it first
+                    Event::FingerDown { .. }
+                    | Event::FingerUp { .. }
+                    | Event::FingerMotion { .. } => {
+                        if let Some(window) = self.windows.values_mut().find(|w| w.focused) {
+                            let (width, height) = window.window.size();
+                            let window_size =
+                                window.window_to_logical(Vector2::new(width as f32, height as f32));
+                            window.inputs.touch.handle_event(&event, window_size);
+                            let (mut ctx, _) =
+                                window.split(&self.render_context, &self.assets, &mut self.shared);
+                            app.on_event(&mut ctx, &event);
+                        }
+                    }
+                    // gamepads have no window, they go to the focused one like the keyboard
+                    Event::ControllerButtonDown { .. }
+                    | Event::ControllerButtonUp { .. }
+                    | Event::ControllerAxisMotion { .. } => {
+                        let _ = self.shared.gamepads.handle_event(&event);
+                        if let Some(window) = self.windows.values_mut().find(|w| w.focused) {
+                            window.inputs.handle_event(&event);
+                            let (mut ctx, _) =
+                                window.split(&self.render_context, &self.assets, &mut self.shared);
+                            app.on_event(&mut ctx, &event);
+                        }
+                    }
+                    // SDL reports per-window scale changes without a window id, so check them all
+                    Event::Display {
+                        display_event: DisplayEvent::ContentScaleChanged,
+                        ..
+                    } => self.refresh_scales(app),
+                    Event::Unknown { type_, .. }
+                        if type_ == sys::events::SDL_EVENT_WINDOW_DISPLAY_SCALE_CHANGED.0 =>
+                    {
+                        self.refresh_scales(app)
+                    }
+                    // the cursor moving over a window during an OS drag, in window coordinates
+                    Event::Unknown { type_, .. }
+                        if type_ == sys::events::SDL_EVENT_DROP_POSITION.0 =>
+                    {
+                        if let Some((window_id, position)) = drop_positions.pop_front()
+                            && let Some(window) = self.windows.get_mut(&window_id)
+                        {
+                            let position = window.window_to_logical(position);
+                            window.inputs.drops.update_hover(position);
+                        }
                     }
                     _ => {}
                 }
             }
         }
+    }
+
+    // SDL keeps sending mouse events to the window the drag started in while the button is held,
+    // even once the cursor leaves it, which is all we need to follow it across windows
+    fn track_drag(&mut self, event: &Event, app: &mut impl SQApp) {
+        match event {
+            Event::MouseMotion {
+                window_id, x, y, ..
+            } => {
+                let (Some(drag), Some(window)) =
+                    (self.shared.drag.as_mut(), self.windows.get(window_id))
+                else {
+                    return;
+                };
+
+                let (wx, wy) = window.window.position();
+                drag.screen_position = Vector2::new(wx as f32 + x, wy as f32 + y);
+                drag.hover = SQ::window_at(&self.windows, drag.screen_position);
+            }
+            Event::MouseButtonUp {
+                mouse_btn: mouse::MouseButton::Left,
+                ..
+            } => {
+                let Some(drag) = self.shared.drag.take() else {
+                    return;
+                };
+
+                let (target, dropped) = match drag.hover {
+                    Some((target, _)) => (target, true),
+                    None => (drag.source, false),
+                };
+                if let Some(window) = self.windows.get_mut(&target) {
+                    let (mut ctx, _) =
+                        window.split(&self.render_context, &self.assets, &mut self.shared);
+                    if dropped {
+                        app.on_drag_drop(&mut ctx, drag);
+                    } else {
+                        app.on_drag_released_outside(&mut ctx, drag);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
 
+    fn deliver_gestures(&mut self, app: &mut impl SQApp) {
         for (_, window) in self.windows.iter_mut() {
-            // window.inputs.update(&self.screens);
+            window.inputs.touch.update(sdl_now());
+            let gestures = window.inputs.touch.take_gestures();
+            if gestures.is_empty() {
+                continue;
+            }
+
+            let (mut ctx, _) = window.split(&self.render_context, &self.assets, &mut self.shared);
+            for gesture in gestures {
+                app.on_gesture(&mut ctx, &gesture);
+            }
         }
     }
 
-    fn render(&mut self) -> anyhow::Result<()> {
+    fn refresh_scales(&mut self, app: &mut impl SQApp) {
         for (_, window) in self.windows.iter_mut() {
+            if let Some(window_event) = window.refresh_scale() {
+                let (mut ctx, _) =
+                    window.split(&self.render_context, &self.assets, &mut self.shared);
+                app.on_window_event(&mut ctx, &window_event);
+            }
+        }
+    }
+
+    fn render(&mut self, app: &mut impl SQApp, alpha: f32) -> anyhow::Result<()> {
+        for (_, window) in self.windows.iter_mut() {
+            // nothing to present to while minimized
+            if window.minimized {
+                continue;
+            }
+
+            let (mut ctx, renderer) =
+                window.split(&self.render_context, &self.assets, &mut self.shared);
+            app.on_render(&mut ctx, renderer, alpha);
+            window.renderer.execute();
+
             match window.render(&self.render_context) {
                 Ok(_) => {}
                 Err(SurfaceError::Lost | SurfaceError::Outdated) => {
-                    let size = window.window.size();
+                    let size = window.window.size_in_pixels();
                     window.resize(&self.render_context.device, size.0, size.1);
                     window.render(&self.render_context)?;
                 }
@@ -170,6 +630,24 @@ impl SQ {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SQWindowEvent {
+    CloseRequested,
+    FocusGained,
+    FocusLost,
+    Minimized,
+    Maximized,
+    Restored,
+    Moved(i32, i32),
+    Resized(f32, f32), // logical size
+    ScaleChanged(f32),
+    DisplayChanged(u32), // moved to another monitor, holds the new display id
+}
+
+// three coordinate spaces are in play:
+// - physical pixels, what the surface is sized in
+// - window coordinates, what SDL reports sizes and mouse positions in
+// - logical units, what layout and input use, physical pixels divided by scale_factor
 pub struct SQWindow {
     pub window: Arc<Window>,
     pub renderer: Renderer,
@@ -177,30 +655,50 @@ pub struct SQWindow {
     pub config: SurfaceConfiguration,
 
     pub inputs: InputManager,
+    pub chrome: Arc<RwLock<WindowChrome>>,
+    pub parent: Option<u32>,
+    hit_test: HitTestRegistration,
 
-    pub size: Vector2<f32>,
+    pub size: Vector2<f32>, // logical
+    pub clear_color: Color,
     pub focused: bool,
     pub minimized: bool,
+    pub scale_factor: f32,  // physical pixels per logical unit
+    pub pixel_density: f32, // physical pixels per window coordinate
+    started: bool,
 }
 impl SQWindow {
     pub fn new(
         render_context: Arc<RenderContext>,
         assets: Arc<AssetManager>,
-        title: &str,
-        width: u32,
-        height: u32,
+        builder: &WindowBuilder,
     ) -> anyhow::Result<Self> {
-        let window = Arc::new(
-            render_context
-                .video_subsystem
-                .window(title, width, height)
-                .high_pixel_density()
-                .position_centered()
-                .borderless()
-                .resizable()
-                .vulkan()
-                .build()?,
-        );
+        let mut window = builder.build_sdl(&render_context)?;
+
+        // sizes are logical, SDL only takes window coordinates
+        let scale_factor = window.display_scale();
+        let pixel_density = window.pixel_density();
+        let points_per_unit = scale_factor / pixel_density;
+        let to_points = |(width, height): (u32, u32)| {
+            (
+                (width as f32 * points_per_unit).round() as u32,
+                (height as f32 * points_per_unit).round() as u32,
+            )
+        };
+        if points_per_unit != 1.0 && builder.fullscreen.is_none() {
+            let (width, height) = to_points((builder.width, builder.height));
+            window.set_size(width, height)?;
+        }
+        if let Some(min_size) = builder.min_size {
+            let (width, height) = to_points(min_size);
+            window.set_minimum_size(width, height)?;
+        }
+        if let Some(max_size) = builder.max_size {
+            let (width, height) = to_points(max_size);
+            window.set_maximum_size(width, height)?;
+        }
+        let window = Arc::new(window);
+        let (pixel_width, pixel_height) = window.size_in_pixels();
 
         let renderer = Renderer::new(render_context.clone(), assets);
 
@@ -215,58 +713,225 @@ impl SQWindow {
             .copied()
             .unwrap_or(caps.formats[0]);
 
+        // transparent windows need the compositor to respect our alpha
+        let alpha_mode = if builder.transparent
+            && caps
+                .alpha_modes
+                .contains(&CompositeAlphaMode::PreMultiplied)
+        {
+            CompositeAlphaMode::PreMultiplied
+        } else {
+            caps.alpha_modes[0]
+        };
+
         // SurfaceConfiguration defines how the surface creates its underlying SurfaceTextures
         let config = SurfaceConfiguration {
             usage: TextureUsages::RENDER_ATTACHMENT,
             format,
-            width,
-            height,
+            width: pixel_width,
+            height: pixel_height,
             present_mode: caps.present_modes[0],
-            alpha_mode: caps.alpha_modes[0],
+            alpha_mode,
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
         };
 
         surface.configure(&render_context.device, &config);
 
+        let mut inputs = InputManager::new();
+        inputs.logical_scale = pixel_density / scale_factor;
+
+        // borderless windows have no native edges to resize from
+        let resize_border = if builder.resizable && !builder.decorations {
+            6.0
+        } else {
+            0.0
+        };
+        let chrome = Arc::new(RwLock::new(WindowChrome::new(resize_border)));
+        let hit_test = HitTestRegistration::new(&window, chrome.clone())?;
+
         Ok(Self {
             window,
             renderer,
             surface,
             config,
-            inputs: InputManager::new(),
+            inputs,
+            chrome,
+            hit_test,
+            parent: None,
 
             size: Vector2 {
-                x: width as f32,
-                y: height as f32,
+                x: pixel_width as f32 / scale_factor,
+                y: pixel_height as f32 / scale_factor,
+            },
+            clear_color: if builder.transparent {
+                Color::TRANSPARENT
+            } else {
+                Color {
+                    r: 0.1,
+                    g: 0.2,
+                    b: 0.3,
+                    a: 1.0,
+                }
             },
             focused: true,
             minimized: false,
+            scale_factor,
+            pixel_density,
+            started: false,
         })
     }
 
+    // width and height in physical pixels
     pub fn resize(&mut self, device: &Device, width: u32, height: u32) {
         if width > 0 && height > 0 {
             self.config.width = width;
             self.config.height = height;
-            self.size = Vector2 {
+            self.size = self.to_logical(Vector2 {
                 x: width as f32,
                 y: height as f32,
-            };
+            });
             self.surface.configure(device, &self.config);
         }
     }
 
-    pub fn handle_event(&mut self, event: &Event, render_context: &RenderContext) {
-        match event {
-            Event::Window {
-                win_event: WindowEvent::Resized(width, height),
-                ..
-            } => {
+    // a handle for SDL calls that need &mut Window, it refers to the same window
+    pub fn handle(&self) -> Window {
+        (*self.window).clone()
+    }
+
+    pub fn window_action(&self, action: WindowAction) -> anyhow::Result<()> {
+        action.apply(&self.window, &self.renderer.render_context)
+    }
+
+    pub fn show(&self) {
+        self.handle().show();
+    }
+
+    pub fn hide(&self) {
+        self.handle().hide();
+    }
+
+    pub fn physical_size(&self) -> (u32, u32) {
+        (self.config.width, self.config.height)
+    }
+
+    // physical pixels -> logical units
+    pub fn to_logical(&self, physical: Vector2<f32>) -> Vector2<f32> {
+        physical / self.scale_factor
+    }
+
+    // logical units -> physical pixels
+    pub fn to_physical(&self, logical: Vector2<f32>) -> Vector2<f32> {
+        logical * self.scale_factor
+    }
+
+    // window coordinates (as in SDL events) -> logical units
+    pub fn window_to_logical(&self, point: Vector2<f32>) -> Vector2<f32> {
+        point * self.inputs.logical_scale
+    }
+
+    // borrows the window as a callback context, alongside its renderer
+    fn split<'a>(
+        &'a mut self,
+        render_context: &'a Arc<RenderContext>,
+        assets: &'a Arc<AssetManager>,
+        shared: &'a mut Shared,
+    ) -> (Context<'a>, &'a mut Renderer) {
+        (
+            Context {
+                window_id: self.window.id(),
+                window: &self.window,
+                render_context,
+                assets,
+                inputs: &mut self.inputs,
+                chrome: &self.chrome,
+                shared,
+            },
+            &mut self.renderer,
+        )
+    }
+
+    // returns the lifecycle changes the event caused, for the application to react to
+    pub fn handle_event(
+        &mut self,
+        event: &Event,
+        render_context: &RenderContext,
+    ) -> Vec<SQWindowEvent> {
+        let Event::Window { win_event, .. } = event else {
+            self.inputs.handle_event(event);
+            return vec![];
+        };
+
+        let mut events = vec![];
+        match win_event {
+            // Resized is in window coordinates, the surface wants pixels
+            WindowEvent::PixelSizeChanged(width, height) => {
                 self.resize(&render_context.device, *width as u32, *height as u32);
+                events.push(SQWindowEvent::Resized(self.size.x, self.size.y));
+            }
+            WindowEvent::CloseRequested => events.push(SQWindowEvent::CloseRequested),
+            WindowEvent::FocusGained => {
+                self.focused = true;
+                self.inputs.reset_states();
+                events.push(SQWindowEvent::FocusGained);
+            }
+            WindowEvent::FocusLost => {
+                // releases never arrive for buttons held while focus moves away
+                self.focused = false;
+                self.inputs.reset_states();
+                events.push(SQWindowEvent::FocusLost);
+            }
+            WindowEvent::Minimized => {
+                self.minimized = true;
+                events.push(SQWindowEvent::Minimized);
             }
-            _ => self.inputs.handle_event(event),
+            WindowEvent::Maximized => {
+                self.minimized = false;
+                events.push(SQWindowEvent::Maximized);
+            }
+            WindowEvent::Restored => {
+                self.minimized = false;
+                events.push(SQWindowEvent::Restored);
+            }
+            WindowEvent::Moved(x, y) => events.push(SQWindowEvent::Moved(*x, *y)),
+            WindowEvent::MouseEnter | WindowEvent::MouseLeave => self
+                .inputs
+                .pointer
+                .handle_event(event, self.inputs.logical_scale),
+            WindowEvent::DisplayChanged(_) => {
+                if let Ok(display) = self.window.get_display() {
+                    events.push(SQWindowEvent::DisplayChanged(display.to_ll()));
+                }
+            }
+            _ => {}
+        }
+
+        // monitors can differ in scale, and a pixel size change may mean the scale changed under us
+        if matches!(
+            win_event,
+            WindowEvent::DisplayChanged(_) | WindowEvent::PixelSizeChanged(..)
+        ) {
+            events.extend(self.refresh_scale());
         }
+        events
+    }
+
+    pub fn refresh_scale(&mut self) -> Option<SQWindowEvent> {
+        let scale = self.window.display_scale();
+        self.pixel_density = self.window.pixel_density();
+        self.inputs.logical_scale = self.pixel_density / scale;
+        if scale == self.scale_factor {
+            return None;
+        }
+
+        self.scale_factor = scale;
+        let (width, height) = self.physical_size();
+        self.size = self.to_logical(Vector2 {
+            x: width as f32,
+            y: height as f32,
+        });
+        Some(SQWindowEvent::ScaleChanged(scale))
     }
 
     fn render(&self, render_context: &RenderContext) -> anyhow::Result<(), SurfaceError> {
@@ -291,12 +956,7 @@ impl SQWindow {
                     view: &texture_view,
                     resolve_target: None,
                     ops: Operations {
-                        load: LoadOp::Clear(Color {
-                            r: 0.1,
-                            g: 0.2,
-                            b: 0.3,
-                            a: 1.0,
-                        }),
+                        load: LoadOp::Clear(self.clear_color),
                         store: StoreOp::Store,
                     },
                 })],
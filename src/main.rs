@@ -1,11 +1,14 @@
 #![allow(unused, unused_variables)]
 use sq::*;
 
+struct Sandbox;
+impl SQApp for Sandbox {}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let mut app = SQ::new().await?;
 
     app.create_window("sq", 800, 600).await?;
-    app.run().await?;
+    app.run(&mut Sandbox).await?;
     Ok(())
 }
@@ -0,0 +1,1074 @@
+use crate::*;
+use std::ops::Range;
+
+use cgmath::Vector2;
+use slotmap::SlotMap;
+use unicode_bidi::{Level, ParagraphBidiInfo};
+use unicode_linebreak::BreakOpportunity;
+use unicode_script::{Script, UnicodeScript};
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+    Justify, // the last line of each paragraph goes to the start side
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextDirection {
+    Auto, // from the first strong character of each paragraph
+    LeftToRight,
+    RightToLeft,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParagraphStyle {
+    pub fonts: Vec<FontKey>, // fallback chain, each character uses the first font that has it
+    pub max_width: Option<f32>, // wraps lines longer than this
+    pub max_lines: Option<usize>, // ends the last line with the ellipsis when there are more
+    pub align: TextAlign,
+    pub line_height: f32, // multiple of the tallest font's line height on each line
+    pub direction: TextDirection,
+    pub ellipsis: String,
+}
+impl ParagraphStyle {
+    pub fn new(fonts: Vec<FontKey>) -> Self {
+        Self {
+            fonts,
+            max_width: None,
+            max_lines: None,
+            align: TextAlign::Left,
+            line_height: 1.0,
+            direction: TextDirection::Auto,
+            ellipsis: "\u{2026}".to_string(),
+        }
+    }
+
+    pub fn with_max_width(mut self, max_width: f32) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    pub fn with_max_lines(mut self, max_lines: usize) -> Self {
+        self.max_lines = Some(max_lines);
+        self
+    }
+
+    pub fn with_align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn with_line_height(mut self, line_height: f32) -> Self {
+        self.line_height = line_height;
+        self
+    }
+
+    pub fn with_direction(mut self, direction: TextDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn with_ellipsis(mut self, ellipsis: impl Into<String>) -> Self {
+        self.ellipsis = ellipsis.into();
+        self
+    }
+}
+
+// a range of the text with its own fallback chain, e.g. a bold span of rich text, an object run
+// covers a single U+FFFC that is laid out as a box of that size sitting on the baseline
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyleRun {
+    pub range: Range<usize>,
+    pub fonts: Vec<FontKey>,
+    pub object: Option<Vector2<f32>>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct ParagraphGlyph {
+    pub font: FontKey,
+    pub index: usize, // byte offset in the text of the first character it was shaped from
+    pub positioned: PositionedGlyph, // relative to the paragraph's top left corner
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct ParagraphObject {
+    pub index: usize,           // byte offset of the U+FFFC in the text
+    pub bounds: Rectangle<f32>, // relative to the paragraph's top left corner
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParagraphLine {
+    pub range: Range<usize>, // bytes of the text, with trailing whitespace and the line break
+    pub x: f32,              // where the visible text starts after alignment
+    pub width: f32,          // of the visible text, trailing whitespace hangs past it
+    pub top: f32,
+    pub baseline: f32,
+    pub height: f32,
+    pub rtl: bool, // the paragraph's base direction
+}
+
+#[derive(Clone, Copy)]
+struct ClusterGlyph {
+    font: FontKey,
+    index: usize,
+    ch: char,
+    glyph: Glyph, // with the offset and advance shaping gave it
+    pen: f32,     // from the start of the cluster
+}
+
+// the smallest piece of text the caret can't go into and lines can't break inside, a grapheme,
+// several that shaping made one glyph of, or an inserted ellipsis
+#[derive(Clone)]
+struct Cluster {
+    range: Range<usize>, // empty for an ellipsis
+    glyphs: Vec<ClusterGlyph>,
+    object: Option<Vector2<f32>>,
+    advance: f32,
+    level: u8, // bidi embedding level, odd levels run right to left
+    whitespace: bool,
+    hard_break: bool,
+    break_after: bool, // a line may wrap after this cluster
+    trailing: bool,    // whitespace at the end of its line
+    x: f32,            // visual position on the line
+    width: f32,        // advance plus what justification added
+}
+impl Cluster {
+    fn rtl(&self) -> bool {
+        self.level % 2 == 1
+    }
+}
+
+// text laid out into lines, runs of one font, direction and script are shaped with rustybuzz,
+// lines break at UAX #14 opportunities and are reordered with the UAX #9 levels of unicode-bidi,
+// breaking comes after shaping, so a word too wide for the line that is split between two
+// clusters keeps the forms it had whole
+pub struct Paragraph {
+    pub text: String,
+    pub glyphs: Vec<ParagraphGlyph>,
+    pub objects: Vec<ParagraphObject>,
+    pub lines: Vec<ParagraphLine>, // at least one, even for empty text
+    pub size: Vector2<f32>,
+    pub truncated: bool,         // lines after max_lines were dropped
+    clusters: Vec<Vec<Cluster>>, // per line, in visual order
+}
+impl Paragraph {
+    pub fn layout(
+        fonts: &mut SlotMap<FontKey, SQFont>,
+        textures: &mut SlotMap<TextureKey, SQTexture>,
+        render_context: &RenderContext,
+        text: &str,
+        style: &ParagraphStyle,
+    ) -> Self {
+        Self::layout_runs(fonts, textures, render_context, text, &[], style)
+    }
+
+    // text outside of runs uses style's fonts
+    pub fn layout_runs(
+        fonts: &mut SlotMap<FontKey, SQFont>,
+        textures: &mut SlotMap<TextureKey, SQTexture>,
+        render_context: &RenderContext,
+        text: &str,
+        runs: &[StyleRun],
+        style: &ParagraphStyle,
+    ) -> Self {
+        let mut shaper = Shaper {
+            fonts,
+            textures,
+            render_context,
+            chain: &style.fonts,
+            runs,
+        };
+        let mut lines = break_lines(text, style, |piece, offset, levels| {
+            shaper.clusters(piece, offset, levels, None)
+        });
+
+        let mut truncated = false;
+        if let Some(max_lines) = style.max_lines
+            && lines.len() > max_lines.max(1)
+        {
+            lines.truncate(max_lines.max(1));
+            truncated = true;
+
+            let (line, range, base_level, ends_paragraph) = lines.last_mut().unwrap();
+            // in the style of the text it cuts off
+            let style_at = line
+                .iter()
+                .rev()
+                .find(|cluster| !(cluster.whitespace || cluster.hard_break))
+                .map_or(range.start, |cluster| cluster.range.start);
+            let levels = vec![*base_level; style.ellipsis.len()];
+            let ellipsis = shaper.clusters(&style.ellipsis, 0, &levels, Some(style_at));
+            let ellipsis_width: f32 = ellipsis.iter().map(|cluster| cluster.advance).sum();
+
+            // the line break and trailing whitespace go first, then whatever doesn't fit
+            while let Some(last) = line.last() {
+                let overflows = style
+                    .max_width
+                    .is_some_and(|max| visible_width(line) + ellipsis_width > max);
+                if !(last.whitespace || last.hard_break || overflows) {
+                    break;
+                }
+                line.pop();
+            }
+            let cut = line.last().map_or(range.start, |cluster| cluster.range.end);
+            for mut cluster in ellipsis {
+                cluster.range = cut..cut;
+                for glyph in &mut cluster.glyphs {
+                    glyph.index = cut;
+                }
+                line.push(cluster);
+            }
+            range.end = cut;
+            *ends_paragraph = true;
+        }
+
+        let container = style.max_width.unwrap_or_else(|| {
+            lines
+                .iter()
+                .map(|(line, ..)| visible_width(line))
+                .fold(0.0, f32::max)
+        });
+
+        let mut paragraph = Self {
+            text: text.to_string(),
+            glyphs: vec![],
+            objects: vec![],
+            lines: vec![],
+            size: Vector2::new(container, 0.0),
+            truncated,
+            clusters: vec![],
+        };
+        let mut top = 0.0;
+        for (mut line, range, base_level, ends_paragraph) in lines {
+            let (x, width) = arrange(&mut line, base_level, ends_paragraph, container, style);
+
+            // half the extra leading goes above the line and half below
+            let (mut ascent, mut descent, mut natural) = (0.0f32, 0.0f32, 0.0f32);
+            let mut used: Vec<FontKey> = line
+                .iter()
+                .flat_map(|cluster| cluster.glyphs.iter().map(|glyph| glyph.font))
+                .collect();
+            if used.is_empty() {
+                used.extend(style.fonts.first());
+            }
+            for font in used.iter().filter_map(|key| shaper.fonts.get(*key)) {
+                ascent = ascent.max(font.metrics.ascent);
+                descent = descent.max(font.metrics.descent);
+                natural = natural.max(font.metrics.line_height);
+            }
+            for size in line.iter().filter_map(|cluster| cluster.object) {
+                ascent = ascent.max(size.y);
+            }
+            let height = natural.max(ascent + descent) * style.line_height;
+            let baseline = top + (height - ascent - descent) / 2.0 + ascent;
+
+            for cluster in &line {
+                if let Some(size) = cluster.object {
+                    paragraph.objects.push(ParagraphObject {
+                        index: cluster.range.start,
+                        bounds: Rectangle {
+                            x: cluster.x,
+                            y: baseline - size.y,
+                            width: size.x,
+                            height: size.y,
+                        },
+                    });
+                }
+                for glyph in &cluster.glyphs {
+                    paragraph.glyphs.push(ParagraphGlyph {
+                        font: glyph.font,
+                        index: glyph.index,
+                        positioned: PositionedGlyph {
+                            ch: glyph.ch,
+                            position: Vector2::new(cluster.x + glyph.pen, baseline)
+                                + glyph.glyph.offset,
+                            glyph: glyph.glyph,
+                        },
+                    });
+                }
+            }
+            paragraph.lines.push(ParagraphLine {
+                range,
+                x,
+                width,
+                top,
+                baseline,
+                height,
+                rtl: base_level % 2 == 1,
+            });
+            paragraph.clusters.push(line);
+            top += height;
+        }
+        paragraph.size.y = top;
+        paragraph
+    }
+
+    // the glyphs to queue with the materials for that font's atlas pages
+    pub fn glyphs_for(&self, font: FontKey) -> Vec<PositionedGlyph> {
+        self.glyphs
+            .iter()
+            .filter(|glyph| glyph.font == font)
+            .map(|glyph| glyph.positioned)
+            .collect()
+    }
+
+    // the line the caret at index is on, the start of the next line where a line wraps
+    pub fn line_of(&self, index: usize) -> usize {
+        self.lines
+            .iter()
+            .rposition(|line| line.range.start <= index)
+            .unwrap_or(0)
+    }
+
+    // the caret index closest to point, relative to the paragraph's top left
+    pub fn hit_test(&self, point: Vector2<f32>) -> usize {
+        let line_index = self
+            .lines
+            .iter()
+            .position(|line| point.y < line.top + line.height)
+            .unwrap_or(self.lines.len() - 1);
+        let line = &self.lines[line_index];
+
+        let clusters: Vec<&Cluster> = self.clusters[line_index]
+            .iter()
+            .filter(|cluster| !cluster.hard_break)
+            .collect();
+        let Some(last) = clusters.last() else {
+            return line.range.start;
+        };
+        // the nearest edge between clusters and the index on either side of it
+        let i = clusters
+            .iter()
+            .position(|cluster| point.x < cluster.x + cluster.width / 2.0)
+            .unwrap_or(clusters.len());
+        let x = clusters
+            .get(i)
+            .map_or(last.x + last.width, |cluster| cluster.x);
+        let before = i.checked_sub(1).map(|i| {
+            let cluster = clusters[i];
+            if cluster.rtl() {
+                cluster.range.start
+            } else {
+                cluster.range.end
+            }
+        });
+        let after = clusters.get(i).map(|cluster| {
+            if cluster.rtl() {
+                cluster.range.end
+            } else {
+                cluster.range.start
+            }
+        });
+        // where the direction changes they differ, the one with its caret at the edge wins
+        [after, before]
+            .into_iter()
+            .flatten()
+            .find(|index| self.caret(*index).x == x)
+            .or(after)
+            .or(before)
+            .unwrap()
+    }
+
+    // a zero width rectangle as tall as the caret's line, relative to the paragraph's top left
+    pub fn caret(&self, index: usize) -> Rectangle<f32> {
+        let line_index = self.line_of(index);
+        let line = &self.lines[line_index];
+        let index = index.min(line.range.end);
+        let clusters = &self.clusters[line_index];
+
+        let starting = clusters
+            .iter()
+            .find(|cluster| !cluster.range.is_empty() && cluster.range.contains(&index));
+        let ending = clusters
+            .iter()
+            .find(|cluster| !cluster.range.is_empty() && cluster.range.end == index);
+        let x = match (starting, ending) {
+            (Some(cluster), _) if !cluster.hard_break => {
+                if cluster.rtl() {
+                    cluster.x + cluster.width
+                } else {
+                    cluster.x
+                }
+            }
+            (_, Some(cluster)) => {
+                if cluster.rtl() {
+                    cluster.x
+                } else {
+                    cluster.x + cluster.width
+                }
+            }
+            _ if line.rtl => line.x + line.width,
+            _ => line.x,
+        };
+        Rectangle {
+            x,
+            y: line.top,
+            width: 0.0,
+            height: line.height,
+        }
+    }
+
+    // one rectangle per visually contiguous piece of the selection, right to left text can make
+    // a single range show up as several pieces on one line
+    pub fn selection_rects(&self, range: Range<usize>) -> Vec<Rectangle<f32>> {
+        self.line_rects(range)
+            .into_iter()
+            .map(|(_, rect)| rect)
+            .collect()
+    }
+
+    // selection_rects with the index of the line each rectangle is on
+    pub fn line_rects(&self, range: Range<usize>) -> Vec<(usize, Rectangle<f32>)> {
+        let mut rects: Vec<(usize, Rectangle<f32>)> = vec![];
+        for (line_index, (line, clusters)) in self.lines.iter().zip(&self.clusters).enumerate() {
+            let mut current: Option<Rectangle<f32>> = None;
+            for cluster in clusters {
+                let selected = cluster.range.start < range.end && range.start < cluster.range.end;
+                if !selected {
+                    rects.extend(current.take().map(|rect| (line_index, rect)));
+                    continue;
+                }
+                match &mut current {
+                    Some(rect) => rect.width = cluster.x + cluster.width - rect.x,
+                    None => {
+                        current = Some(Rectangle {
+                            x: cluster.x,
+                            y: line.top,
+                            width: cluster.width,
+                            height: line.height,
+                        })
+                    }
+                }
+            }
+            rects.extend(current.map(|rect| (line_index, rect)));
+        }
+        rects
+    }
+}
+
+struct Shaper<'a> {
+    fonts: &'a mut SlotMap<FontKey, SQFont>,
+    textures: &'a mut SlotMap<TextureKey, SQTexture>,
+    render_context: &'a RenderContext,
+    chain: &'a [FontKey],
+    runs: &'a [StyleRun],
+}
+impl<'a> Shaper<'a> {
+    fn run_at(&self, index: usize) -> Option<&'a StyleRun> {
+        self.runs.iter().find(|run| run.range.contains(&index))
+    }
+
+    // characters no font has are shaped with the first, which draws its missing glyph box
+    fn font_for(&self, ch: char, chain: &[FontKey]) -> Option<FontKey> {
+        chain
+            .iter()
+            .find(|key| self.fonts.get(**key).is_some_and(|font| font.has_glyph(ch)))
+            .or(chain.first())
+            .copied()
+    }
+
+    fn glyph(&mut self, font: FontKey, id: u16) -> Option<Glyph> {
+        self.fonts
+            .get_mut(font)?
+            .glyph(id, self.render_context, self.textures)
+            .ok()
+    }
+
+    // levels has one entry per byte of text, offset is where text starts in the paragraph,
+    // style_at styles all of text like that index of the paragraph instead of its own runs
+    fn clusters(
+        &mut self,
+        text: &str,
+        offset: usize,
+        levels: &[u8],
+        style_at: Option<usize>,
+    ) -> Vec<Cluster> {
+        let mut clusters: Vec<Cluster> = vec![];
+        // graphemes gathered for shaping, each run has one font, level and script
+        let mut shaping: Option<(Range<usize>, FontKey, Option<Script>)> = None;
+        for (index, grapheme) in text.grapheme_indices(true) {
+            let ch = grapheme.chars().next().unwrap();
+            let run = self.run_at(style_at.unwrap_or(offset + index));
+            let chain = run.map_or(self.chain, |run| &run.fonts);
+            let object = run
+                .and_then(|run| run.object)
+                .filter(|_| ch == '\u{FFFC}' && style_at.is_none());
+            let font = self.font_for(ch, chain);
+
+            if let Some(font) = font
+                && object.is_none()
+                && !ch.is_control()
+                && !is_hard_break(ch)
+            {
+                let script = grapheme.chars().map(|ch| ch.script()).find(|script| {
+                    !matches!(script, Script::Common | Script::Inherited | Script::Unknown)
+                });
+                // common characters, e.g. spaces and punctuation, go with the script around them
+                match &mut shaping {
+                    Some((range, run_font, run_script))
+                        if *run_font == font
+                            && levels[range.start] == levels[index]
+                            && (script.is_none()
+                                || run_script.is_none()
+                                || *run_script == script) =>
+                    {
+                        range.end = index + grapheme.len();
+                        *run_script = run_script.or(script);
+                    }
+                    _ => {
+                        if let Some((range, font, _)) = shaping.take() {
+                            self.shape(text, range, font, offset, levels, &mut clusters);
+                        }
+                        shaping = Some((index..index + grapheme.len(), font, script));
+                    }
+                }
+                continue;
+            }
+
+            if let Some((range, font, _)) = shaping.take() {
+                self.shape(text, range, font, offset, levels, &mut clusters);
+            }
+            let advance = match object {
+                Some(size) => size.x,
+                None if ch == '\t' => {
+                    let space = font.and_then(|font| self.fonts.get(font));
+                    space.map_or(0.0, |font| font.advance(' ') * 4.0)
+                }
+                None => 0.0,
+            };
+            clusters.push(Cluster {
+                range: offset + index..offset + index + grapheme.len(),
+                glyphs: vec![],
+                object,
+                advance,
+                level: levels[index],
+                whitespace: is_breaking_space(ch),
+                hard_break: grapheme.chars().any(is_hard_break),
+                break_after: false,
+                trailing: false,
+                x: 0.0,
+                width: 0.0,
+            });
+        }
+        if let Some((range, font, _)) = shaping.take() {
+            self.shape(text, range, font, offset, levels, &mut clusters);
+        }
+
+        let breaks = break_opportunities(text);
+        for cluster in &mut clusters {
+            cluster.break_after = breaks.binary_search(&(cluster.range.end - offset)).is_ok();
+        }
+        clusters
+    }
+
+    // shapes range of text and adds its clusters, which are graphemes unless shaping made one
+    // glyph of several, e.g. a ligature, then they're a single cluster the caret can't go into
+    fn shape(
+        &mut self,
+        text: &str,
+        range: Range<usize>,
+        font: FontKey,
+        offset: usize,
+        levels: &[u8],
+        clusters: &mut Vec<Cluster>,
+    ) {
+        let run = &text[range.clone()];
+        let level = levels[range.start];
+        let shaped = self.fonts.get(font).map_or(vec![], |sq_font| {
+            sq_font.shape(
+                run,
+                &text[..range.start],
+                &text[range.end..],
+                level % 2 == 1,
+            )
+        });
+
+        let mut starts: Vec<usize> = shaped.iter().map(|glyph| glyph.cluster).collect();
+        starts.sort_unstable();
+        let bounds: Vec<usize> = run
+            .grapheme_indices(true)
+            .map(|(index, _)| index)
+            .filter(|index| *index == 0 || starts.binary_search(index).is_ok())
+            .collect();
+        let first = clusters.len();
+        for (i, start) in bounds.iter().enumerate() {
+            let end = bounds.get(i + 1).copied().unwrap_or(run.len());
+            clusters.push(Cluster {
+                range: offset + range.start + start..offset + range.start + end,
+                glyphs: vec![],
+                object: None,
+                advance: 0.0,
+                level: levels[range.start + start],
+                whitespace: run[*start..].starts_with(is_breaking_space),
+                hard_break: false,
+                break_after: false,
+                trailing: false,
+                x: 0.0,
+                width: 0.0,
+            });
+        }
+
+        // glyphs come in visual order, so each cluster's are left to right
+        for shaped in shaped {
+            let i = bounds.partition_point(|start| *start <= shaped.cluster) - 1;
+            let glyph = self.glyph(font, shaped.id);
+            let cluster = &mut clusters[first + i];
+            if let Some(mut glyph) = glyph {
+                glyph.offset += shaped.offset;
+                glyph.advance = shaped.advance;
+                cluster.glyphs.push(ClusterGlyph {
+                    font,
+                    index: offset + range.start + shaped.cluster,
+                    ch: run[shaped.cluster..].chars().next().unwrap(),
+                    glyph,
+                    pen: cluster.advance,
+                });
+            }
+            cluster.advance += shaped.advance;
+        }
+    }
+}
+
+// splits text into paragraphs at its hard breaks and wraps each one, clusters shapes a paragraph
+// given its offset in text and the level of each of its bytes, lines come in logical order with
+// their range of text, their paragraph's base level and whether their paragraph ends with them
+fn break_lines(
+    text: &str,
+    style: &ParagraphStyle,
+    mut clusters: impl FnMut(&str, usize, &[u8]) -> Vec<Cluster>,
+) -> Vec<(Vec<Cluster>, Range<usize>, u8, bool)> {
+    let mut lines = vec![];
+    for range in paragraphs(text) {
+        let piece = &text[range.clone()];
+        let bidi = resolve_levels(piece, style.direction);
+        let levels: Vec<u8> = bidi.levels.iter().map(|level| level.number()).collect();
+
+        let wrapped = wrap(clusters(piece, range.start, &levels), style.max_width);
+        let count = wrapped.len();
+        let mut line_start = range.start;
+        for (i, mut line) in wrapped.into_iter().enumerate() {
+            let line_end = if i + 1 == count {
+                range.end
+            } else {
+                line.last().map_or(line_start, |cluster| cluster.range.end)
+            };
+            // whitespace at the end of the line or before a tab goes back to the base level
+            let reordered = bidi.reordered_levels(line_start - range.start..line_end - range.start);
+            for cluster in &mut line {
+                cluster.level = reordered[cluster.range.start - range.start].number();
+            }
+            let base_level = bidi.paragraph_level.number();
+            lines.push((line, line_start..line_end, base_level, i + 1 == count));
+            line_start = line_end;
+        }
+    }
+    lines
+}
+
+// the text up to and including each hard break, and after a final one an empty paragraph for
+// the caret to sit on
+fn paragraphs(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut start = 0;
+    for (end, opportunity) in unicode_linebreak::linebreaks(text) {
+        if opportunity == BreakOpportunity::Mandatory && end > start {
+            ranges.push(start..end);
+            start = end;
+        }
+    }
+    if text.is_empty() || text.ends_with(is_hard_break) {
+        ranges.push(text.len()..text.len());
+    }
+    ranges
+}
+
+// the UAX #9 levels of one paragraph, Auto takes the direction of its first strong character
+fn resolve_levels(text: &str, direction: TextDirection) -> ParagraphBidiInfo<'_> {
+    let level = match direction {
+        TextDirection::Auto => None,
+        TextDirection::LeftToRight => Some(Level::ltr()),
+        TextDirection::RightToLeft => Some(Level::rtl()),
+    };
+    ParagraphBidiInfo::new(text, level)
+}
+
+// the byte offsets in text a line can wrap at under UAX #14, the end of the text is one
+fn break_opportunities(text: &str) -> Vec<usize> {
+    unicode_linebreak::linebreaks(text)
+        .map(|(index, _)| index)
+        .collect()
+}
+
+// greedy, each line takes as many clusters as fit and goes back to the last break opportunity
+fn wrap(clusters: Vec<Cluster>, max_width: Option<f32>) -> Vec<Vec<Cluster>> {
+    let mut lines = vec![];
+    let mut line: Vec<Cluster> = vec![];
+    for cluster in clusters {
+        if let Some(max) = max_width
+            && !(cluster.whitespace || cluster.hard_break)
+        {
+            while !line.is_empty() && visible_width(&line) + cluster.advance > max {
+                // a word wider than the line breaks between clusters
+                let split = line
+                    .iter()
+                    .rposition(|cluster| cluster.break_after)
+                    .map_or(line.len(), |i| i + 1);
+                let rest = line.split_off(split);
+                lines.push(std::mem::replace(&mut line, rest));
+            }
+        }
+        line.push(cluster);
+    }
+    lines.push(line);
+    lines
+}
+
+// trailing whitespace and the line break don't count
+fn visible_width(line: &[Cluster]) -> f32 {
+    let visible = line
+        .iter()
+        .rposition(|cluster| !(cluster.whitespace || cluster.hard_break))
+        .map_or(0, |i| i + 1);
+    line[..visible].iter().map(|cluster| cluster.advance).sum()
+}
+
+// puts a line in visual order and positions its clusters, returns where the visible text starts
+// after alignment and how wide it is after justification
+fn arrange(
+    line: &mut [Cluster],
+    base_level: u8,
+    ends_paragraph: bool,
+    container: f32,
+    style: &ParagraphStyle,
+) -> (f32, f32) {
+    // trailing whitespace takes the paragraph's direction so it hangs off the end
+    for cluster in line.iter_mut().rev() {
+        if !(cluster.whitespace || cluster.hard_break) {
+            break;
+        }
+        cluster.level = base_level;
+        cluster.trailing = true;
+    }
+    let rtl = base_level % 2 == 1;
+    let width = visible_width(line);
+    let trailing_width: f32 = line
+        .iter()
+        .filter(|cluster| cluster.trailing)
+        .map(|cluster| cluster.advance)
+        .sum();
+
+    let spaces = line
+        .iter()
+        .filter(|cluster| cluster.whitespace && !cluster.trailing)
+        .count();
+    let justify = style.align == TextAlign::Justify
+        && style.max_width.is_some()
+        && !ends_paragraph
+        && spaces > 0;
+    let extra = if justify {
+        (container - width).max(0.0) / spaces as f32
+    } else {
+        0.0
+    };
+    let (x, width) = match style.align {
+        _ if justify => (0.0, width + extra * spaces as f32),
+        TextAlign::Left => (0.0, width),
+        TextAlign::Center => ((container - width) / 2.0, width),
+        TextAlign::Right => (container - width, width),
+        TextAlign::Justify if rtl => (container - width, width),
+        TextAlign::Justify => (0.0, width),
+    };
+
+    reorder(line);
+    let mut pen = if rtl { x - trailing_width } else { x };
+    for cluster in line {
+        cluster.x = pen;
+        cluster.width = cluster.advance;
+        if cluster.whitespace && !cluster.trailing {
+            cluster.width += extra;
+        }
+        pen += cluster.width;
+    }
+    (x, width)
+}
+
+// UAX #9 rule L2, reverses every run at or above each odd level, from the highest level down
+fn reorder(line: &mut [Cluster]) {
+    let (Some(lowest), Some(highest)) = (
+        line.iter().map(|cluster| cluster.level).min(),
+        line.iter().map(|cluster| cluster.level).max(),
+    ) else {
+        return;
+    };
+    let lowest_odd = lowest | 1;
+    for level in (lowest_odd..=highest).rev() {
+        let mut i = 0;
+        while i < line.len() {
+            if line[i].level < level {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < line.len() && line[i].level >= level {
+                i += 1;
+            }
+            line[start..i].reverse();
+        }
+    }
+}
+
+// the characters UAX #14 always breaks after
+fn is_hard_break(ch: char) -> bool {
+    matches!(
+        ch,
+        '\n' | '\r' | '\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+// whitespace a line can wrap after, no-break spaces glue words together
+fn is_breaking_space(ch: char) -> bool {
+    ch.is_whitespace() && !is_hard_break(ch) && !matches!(ch, '\u{A0}' | '\u{2007}' | '\u{202F}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every grapheme is 10 wide and line breaks take no space, like a monospaced font
+    fn clusters(text: &str, offset: usize, levels: &[u8]) -> Vec<Cluster> {
+        let breaks = break_opportunities(text);
+        text.grapheme_indices(true)
+            .map(|(index, grapheme)| {
+                let hard_break = grapheme.chars().any(is_hard_break);
+                let end = index + grapheme.len();
+                Cluster {
+                    range: offset + index..offset + end,
+                    glyphs: vec![],
+                    object: None,
+                    advance: if hard_break { 0.0 } else { 10.0 },
+                    level: levels[index],
+                    whitespace: grapheme.starts_with(is_breaking_space),
+                    hard_break,
+                    break_after: breaks.binary_search(&end).is_ok(),
+                    trailing: false,
+                    x: 0.0,
+                    width: 0.0,
+                }
+            })
+            .collect()
+    }
+
+    // Paragraph::layout_runs with lines 10 high, in a container 100 wide without a max_width
+    fn layout(text: &str, style: &ParagraphStyle) -> Paragraph {
+        let container = style.max_width.unwrap_or(100.0);
+        let mut paragraph = Paragraph {
+            text: text.to_string(),
+            glyphs: vec![],
+            objects: vec![],
+            lines: vec![],
+            size: Vector2::new(container, 0.0),
+            truncated: false,
+            clusters: vec![],
+        };
+        for (mut line, range, base_level, ends_paragraph) in break_lines(text, style, clusters) {
+            let (x, width) = arrange(&mut line, base_level, ends_paragraph, container, style);
+            paragraph.lines.push(ParagraphLine {
+                range,
+                x,
+                width,
+                top: paragraph.size.y,
+                baseline: paragraph.size.y + 8.0,
+                height: 10.0,
+                rtl: base_level % 2 == 1,
+            });
+            paragraph.clusters.push(line);
+            paragraph.size.y += 10.0;
+        }
+        paragraph
+    }
+
+    fn style() -> ParagraphStyle {
+        ParagraphStyle::new(vec![])
+    }
+
+    fn lines(paragraph: &Paragraph) -> Vec<&str> {
+        paragraph
+            .lines
+            .iter()
+            .map(|line| &paragraph.text[line.range.clone()])
+            .collect()
+    }
+
+    // each line's text left to right as it shows
+    fn visual(paragraph: &Paragraph) -> Vec<String> {
+        paragraph
+            .clusters
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .map(|cluster| &paragraph.text[cluster.range.clone()])
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn paragraphs_end_after_hard_breaks() {
+        assert_eq!(paragraphs(""), vec![0..0]);
+        assert_eq!(paragraphs("ab"), vec![0..2]);
+        assert_eq!(paragraphs("a\r\nb"), vec![0..3, 3..4]);
+        assert_eq!(paragraphs("a\n"), vec![0..2, 2..2]);
+        assert_eq!(paragraphs("a\u{2028}b\u{2029}"), vec![0..4, 4..8, 8..8]);
+    }
+
+    #[test]
+    fn break_opportunities_follow_uax14() {
+        assert_eq!(break_opportunities("hello world"), vec![6, 11]);
+        // after a hyphen, but not before closing punctuation or inside a number
+        assert_eq!(break_opportunities("well-known"), vec![5, 10]);
+        assert_eq!(break_opportunities("(hi) there!"), vec![5, 11]);
+        assert_eq!(break_opportunities("1,000.5"), vec![7]);
+        assert_eq!(break_opportunities("a\u{A0}b"), vec![4]);
+        // between ideographs, but not before a small kana or an ideographic full stop
+        assert_eq!(break_opportunities("日本語"), vec![3, 6, 9]);
+        assert_eq!(break_opportunities("ちょっと。"), vec![9, 15]);
+    }
+
+    #[test]
+    fn wraps_at_break_opportunities() {
+        let style = style().with_max_width(100.0);
+        let paragraph = layout("the quick brown fox jumps", &style);
+        assert_eq!(lines(&paragraph), ["the quick ", "brown fox ", "jumps"]);
+        // trailing whitespace hangs past the width
+        assert_eq!(paragraph.lines[0].width, 90.0);
+
+        let paragraph = layout("well-known", &style.clone().with_max_width(60.0));
+        assert_eq!(lines(&paragraph), ["well-", "known"]);
+
+        // a word wider than the line breaks between clusters
+        let paragraph = layout("abcdefghijkl", &style.clone().with_max_width(50.0));
+        assert_eq!(lines(&paragraph), ["abcde", "fghij", "kl"]);
+
+        let paragraph = layout("日本語", &style.clone().with_max_width(20.0));
+        assert_eq!(lines(&paragraph), ["日本", "語"]);
+
+        let paragraph = layout("one\r\ntwo\n", &style);
+        assert_eq!(lines(&paragraph), ["one\r\n", "two\n", ""]);
+    }
+
+    #[test]
+    fn resolves_mixed_levels() {
+        let numbers = |text| -> Vec<u8> {
+            let bidi = resolve_levels(text, TextDirection::Auto);
+            text.char_indices()
+                .map(|(index, _)| bidi.levels[index].number())
+                .collect()
+        };
+        assert_eq!(numbers("ab אב"), [0, 0, 0, 1, 1]);
+        // numbers in right to left text stay left to right, one level up
+        assert_eq!(numbers("אב 12"), [1, 1, 1, 2, 2]);
+        assert_eq!(numbers("ab (אב) cd"), [0, 0, 0, 0, 1, 1, 0, 0, 0, 0]);
+
+        assert_eq!(
+            resolve_levels("אב ab", TextDirection::Auto).paragraph_level,
+            Level::rtl()
+        );
+        assert_eq!(
+            resolve_levels("12 אב", TextDirection::Auto).paragraph_level,
+            Level::rtl()
+        );
+        assert_eq!(
+            resolve_levels("אב ab", TextDirection::LeftToRight).paragraph_level,
+            Level::ltr()
+        );
+    }
+
+    #[test]
+    fn reorders_mixed_text() {
+        let paragraph = layout("abc אבג def", &style());
+        assert_eq!(visual(&paragraph), ["abc גבא def"]);
+
+        // numbers after left to right text are left to right too
+        let paragraph = layout("אבג abc 123!", &style());
+        assert_eq!(visual(&paragraph), ["!abc 123 גבא"]);
+        assert!(paragraph.lines[0].rtl);
+
+        // each line is reordered on its own, trailing whitespace goes to the end of the line
+        let style = style()
+            .with_direction(TextDirection::RightToLeft)
+            .with_max_width(80.0);
+        let paragraph = layout("abc אבג def", &style);
+        assert_eq!(visual(&paragraph), [" גבא abc", "def"]);
+    }
+
+    #[test]
+    fn caret_and_hit_test_round_trip() {
+        let texts = [
+            "hello world",
+            "שלום עולם",
+            "abc אבג def",
+            "אבג abc 123",
+            "one\ntwo\n",
+            "e\u{301}t\u{E9}",
+        ];
+        for text in texts {
+            for max_width in [None, Some(60.0)] {
+                let mut style = style();
+                style.max_width = max_width;
+                let paragraph = layout(text, &style);
+                let carets: Vec<(usize, Rectangle<f32>)> = text
+                    .grapheme_indices(true)
+                    .map(|(index, _)| index)
+                    .chain([text.len()])
+                    .map(|index| (index, paragraph.caret(index)))
+                    .collect();
+                for &(index, caret) in &carets {
+                    // where the direction changes two carets can be in the same place
+                    if carets
+                        .iter()
+                        .any(|(other, rect)| *other != index && *rect == caret)
+                    {
+                        continue;
+                    }
+                    let point = Vector2::new(caret.x, caret.y + caret.height / 2.0);
+                    assert_eq!(
+                        paragraph.hit_test(point),
+                        index,
+                        "{:?} at {:?}",
+                        text,
+                        max_width
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn carets_in_mixed_text() {
+        let paragraph = layout("abc אבג def", &style());
+        let x = |index| paragraph.caret(index).x;
+        // the right to left word starts on its right
+        assert_eq!(x(4), 70.0);
+        assert_eq!(x(8), 50.0);
+        assert_eq!(x(11), 80.0);
+        assert_eq!(x(14), 110.0);
+
+        // the left half of a right to left character is after it
+        assert_eq!(paragraph.hit_test(Vector2::new(52.0, 5.0)), 8);
+        assert_eq!(paragraph.hit_test(Vector2::new(58.0, 5.0)), 6);
+        assert_eq!(paragraph.hit_test(Vector2::new(500.0, 5.0)), 14);
+        assert_eq!(paragraph.hit_test(Vector2::new(-5.0, 5.0)), 0);
+    }
+
+    #[test]
+    fn selections_split_at_direction_changes() {
+        let paragraph = layout("abc אבג def", &style());
+        let rects = paragraph.selection_rects(2..6);
+        let spans: Vec<(f32, f32)> = rects.iter().map(|rect| (rect.x, rect.width)).collect();
+        // "c " then "א" on the far side of the right to left word
+        assert_eq!(spans, [(20.0, 20.0), (60.0, 10.0)]);
+    }
+}
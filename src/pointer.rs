@@ -0,0 +1,220 @@
+use crate::*;
+use std::collections::{HashMap, hash_map::Entry};
+
+use anyhow::anyhow;
+use cgmath::Vector2;
+use image::RgbaImage;
+use sdl3::{
+    event::{Event, WindowEvent},
+    mouse::Cursor,
+    pixels::PixelFormat,
+    surface::Surface,
+    video::Window,
+};
+use slotmap::SlotMap;
+
+pub use sdl3::mouse::SystemCursor;
+
+slotmap::new_key_type! { pub struct CursorRegionKey; }
+slotmap::new_key_type! { pub struct CustomCursorKey; }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorShape {
+    System(SystemCursor), // e.g. IBeam over text, Hand over links, SizeWE over a splitter
+    Custom(CustomCursorKey),
+    Hidden,
+}
+
+// an area of the window that shows its own cursor while hovered
+pub struct CursorRegion {
+    pub bounds: Rectangle<f32>, // logical
+    pub z_order: i32,           // the highest region under the cursor wins
+    pub cursor: CursorShape,
+}
+
+// SDL has one cursor for the whole application, each window sets it while the cursor is over it
+pub struct PointerManager {
+    pub regions: SlotMap<CursorRegionKey, CursorRegion>,
+    pub default_cursor: CursorShape,
+    pub hovered: bool,       // the cursor is inside the window
+    pub delta: Vector2<f32>, // logical, motion since the last tick, the only motion in relative mode
+    custom: SlotMap<CustomCursorKey, Cursor>,
+    system: HashMap<SystemCursor, Cursor>,
+    applied: Option<CursorShape>,
+    captured: bool,
+    relative: bool,
+}
+impl PointerManager {
+    pub fn new() -> Self {
+        Self {
+            regions: SlotMap::with_key(),
+            default_cursor: CursorShape::System(SystemCursor::Arrow),
+            hovered: false,
+            delta: Vector2::new(0.0, 0.0),
+            custom: SlotMap::with_key(),
+            system: HashMap::new(),
+            applied: None,
+            captured: false,
+            relative: false,
+        }
+    }
+
+    pub fn add_region(&mut self, region: CursorRegion) -> CursorRegionKey {
+        self.regions.insert(region)
+    }
+
+    pub fn remove_region(&mut self, key: CursorRegionKey) {
+        self.regions.remove(key);
+    }
+
+    pub fn cursor_at(&self, position: Vector2<f32>) -> CursorShape {
+        self.regions
+            .values()
+            .filter(|region| region.bounds.contains(position))
+            .max_by_key(|region| region.z_order)
+            .map_or(self.default_cursor, |region| region.cursor)
+    }
+
+    // hot_x and hot_y are the pixel that points, e.g. the tip of an arrow
+    pub fn add_custom(
+        &mut self,
+        image: &RgbaImage,
+        hot_x: u32,
+        hot_y: u32,
+    ) -> anyhow::Result<CustomCursorKey> {
+        let mut pixels = image.as_raw().clone();
+        let surface = Surface::from_data(
+            &mut pixels,
+            image.width(),
+            image.height(),
+            image.width() * 4,
+            PixelFormat::try_from(sdl3::sys::pixels::SDL_PIXELFORMAT_RGBA32)?,
+        )?;
+        // SDL copies the pixels, the surface can go right after
+        let cursor = Cursor::from_surface(surface, hot_x as i32, hot_y as i32)?;
+        Ok(self.custom.insert(cursor))
+    }
+
+    pub fn add_custom_texture(
+        &mut self,
+        texture: &SQTexture,
+        render_context: &RenderContext,
+        hot_x: u32,
+        hot_y: u32,
+    ) -> anyhow::Result<CustomCursorKey> {
+        let image = texture.to_image(render_context)?;
+        self.add_custom(&image, hot_x, hot_y)
+    }
+
+    pub fn remove_custom(&mut self, key: CustomCursorKey) {
+        self.custom.remove(key);
+        if self.applied == Some(CursorShape::Custom(key)) {
+            self.applied = None;
+        }
+    }
+
+    // keeps motion coming while the cursor is outside the window, SDL already does this on its
+    // own while a mouse button is held, this is for drags that started some other way
+    pub fn set_capture(&mut self, capture: bool) -> anyhow::Result<()> {
+        // Safety: takes no pointers, SDL has been initialised by SQ
+        if unsafe { sdl3::sys::mouse::SDL_CaptureMouse(capture) } {
+            self.captured = capture;
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Failed to capture the mouse: {}",
+                sdl3::get_error()
+            ))
+        }
+    }
+
+    pub fn is_captured(&self) -> bool {
+        self.captured
+    }
+
+    // hides and locks the cursor, only delta changes, e.g. for orbiting a 3D viewport
+    pub fn set_relative(&mut self, window: &Window, relative: bool) -> anyhow::Result<()> {
+        // Safety: the window is alive for the duration of the call
+        if unsafe { sdl3::sys::mouse::SDL_SetWindowRelativeMouseMode(window.raw(), relative) } {
+            self.relative = relative;
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Failed to set relative mouse mode: {}",
+                sdl3::get_error()
+            ))
+        }
+    }
+
+    pub fn is_relative(&self) -> bool {
+        self.relative
+    }
+
+    pub fn handle_event(&mut self, event: &Event, logical_scale: f32) {
+        match event {
+            Event::Window {
+                win_event: WindowEvent::MouseEnter,
+                ..
+            } => {
+                self.hovered = true;
+                // another window may have changed the cursor in the meantime
+                self.applied = None;
+            }
+            Event::Window {
+                win_event: WindowEvent::MouseLeave,
+                ..
+            } => self.hovered = false,
+            Event::MouseMotion { xrel, yrel, .. } => {
+                self.delta += Vector2::new(*xrel, *yrel) * logical_scale;
+            }
+            _ => {}
+        }
+    }
+
+    // shows the cursor of whatever is under position, logical
+    pub fn update(&mut self, position: Option<Vector2<f32>>) {
+        if !self.hovered || self.relative {
+            return;
+        }
+        let shape = position.map_or(self.default_cursor, |position| self.cursor_at(position));
+        if self.applied == Some(shape) {
+            return;
+        }
+
+        let cursor = match shape {
+            CursorShape::System(system) => match self.system.entry(system) {
+                Entry::Occupied(entry) => Some(&*entry.into_mut()),
+                Entry::Vacant(entry) => match Cursor::from_system(system) {
+                    Ok(cursor) => Some(&*entry.insert(cursor)),
+                    // a shape the platform doesn't have keeps the current cursor
+                    Err(_) => None,
+                },
+            },
+            CursorShape::Custom(key) => self.custom.get(key),
+            CursorShape::Hidden => None,
+        };
+
+        // Safety: takes no pointers, SDL has been initialised by SQ
+        unsafe {
+            if shape == CursorShape::Hidden {
+                sdl3::sys::mouse::SDL_HideCursor();
+            } else {
+                sdl3::sys::mouse::SDL_ShowCursor();
+            }
+        }
+        if let Some(cursor) = cursor {
+            cursor.set();
+        }
+        self.applied = Some(shape);
+    }
+
+    pub fn tick(&mut self) {
+        self.delta = Vector2::new(0.0, 0.0);
+    }
+
+    // SDL drops the capture itself when the window loses focus
+    pub fn reset(&mut self) {
+        self.captured = false;
+        self.delta = Vector2::new(0.0, 0.0);
+    }
+}
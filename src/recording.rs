@@ -0,0 +1,946 @@
+use crate::*;
+use std::{collections::VecDeque, path::Path, str::FromStr};
+
+use anyhow::anyhow;
+use sdl3::{
+    event::{Event, WindowEvent},
+    keyboard::{Keycode, Mod, Scancode},
+    mouse::{MouseButton, MouseState, MouseWheelDirection},
+    pen::PenAxis,
+    sys::pen::SDL_PenAxis,
+};
+
+// one event as SQ::handle_events received it
+#[derive(Debug, Clone)]
+pub struct RecordedEvent {
+    pub frame: u64, // the update it was handled in, counted from the start of the recording
+    pub time: u64,  // nanoseconds since the start of the recording
+    pub window_id: Option<u32>,
+    pub event: Event,
+}
+
+// how long an update was and how many fixed ticks it ran, so a replay steps the same way
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecordedFrame {
+    pub dt: f32,
+    pub ticks: u32, // always 1 with Timestep::Variable
+}
+
+// user input and window events in the order they arrived, plus the timing of every frame,
+// window events are only kept for reference and aren't replayed, neither are gamepads being
+// plugged in since there is nothing to open on replay, their buttons and axes still are
+#[derive(Debug, Clone, Default)]
+pub struct InputRecording {
+    pub events: Vec<RecordedEvent>,
+    pub frames: Vec<RecordedFrame>, // indexed by RecordedEvent::frame
+}
+impl InputRecording {
+    // one "frame time window Event fields..." per line, window is "-" for global events,
+    // text is always the last field and runs to the end of the line, each frame's events are
+    // followed by a "frame Frame dt ticks" line
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let mut events = self.events.iter().peekable();
+        for (frame, timing) in self.frames.iter().enumerate() {
+            while let Some(recorded) = events.next_if(|recorded| recorded.frame <= frame as u64) {
+                text += &event_line(recorded);
+            }
+            text += &format!("{} Frame {} {}\n", frame, timing.dt, timing.ticks);
+        }
+        // the frame recording was stopped in
+        for recorded in events {
+            text += &event_line(recorded);
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut recording = Self::default();
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |e| anyhow!("Line {}: {}", i + 1, e);
+            match parse_frame_line(line) {
+                Some(frame) => {
+                    let (index, timing) = frame.map_err(error)?;
+                    if index != recording.frames.len() as u64 {
+                        return Err(error(anyhow!("Frame {} out of order", index)));
+                    }
+                    recording.frames.push(timing);
+                }
+                None => recording.events.push(parse_line(line).map_err(error)?),
+            }
+        }
+        Ok(recording)
+    }
+
+    pub fn save_file(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        std::fs::write(path, self.to_text())?;
+        Ok(())
+    }
+
+    pub fn load_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text)
+    }
+}
+
+pub struct InputRecorder {
+    pub recording: InputRecording,
+    started: u64,
+    frame: u64,
+}
+impl InputRecorder {
+    pub fn new() -> Self {
+        Self {
+            recording: InputRecording::default(),
+            started: sdl_now(),
+            frame: 0,
+        }
+    }
+
+    pub fn record(&mut self, event: &Event) {
+        if encode(event).is_none() {
+            return;
+        }
+        self.recording.events.push(RecordedEvent {
+            frame: self.frame,
+            time: event.get_timestamp().saturating_sub(self.started),
+            window_id: SQ::get_window_id(event),
+            event: event.clone(),
+        });
+    }
+
+    // called once the frame's updates have run
+    pub fn end_frame(&mut self, dt: f32, ticks: u32) {
+        self.recording.frames.push(RecordedFrame { dt, ticks });
+        self.frame += 1;
+    }
+}
+
+// hands a recording back out frame by frame, so the app sees the same events in the same
+// updates no matter how long the frames take
+pub struct InputReplay {
+    events: VecDeque<RecordedEvent>,
+    frames: Vec<RecordedFrame>,
+    started: u64,
+    frame: u64,
+}
+impl InputReplay {
+    pub fn new(recording: InputRecording) -> Self {
+        Self {
+            events: recording.events.into(),
+            frames: recording.frames,
+            started: sdl_now(),
+            frame: 0,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.events.is_empty() && self.frame >= self.frames.len() as u64
+    }
+
+    // the timing of the frame next_frame hands out next, None past the recorded frames
+    pub fn frame(&self) -> Option<RecordedFrame> {
+        self.frames.get(self.frame as usize).copied()
+    }
+
+    // only input is fed back, the windows send their own window events,
+    // timestamps are moved to the present so held durations still line up with sdl_now
+    pub fn next_frame(&mut self) -> Vec<Event> {
+        let mut events = vec![];
+        while let Some(recorded) = self.events.front() {
+            if recorded.frame > self.frame {
+                break;
+            }
+            let recorded = self.events.pop_front().unwrap();
+            if !is_user_input(&recorded.event) {
+                continue;
+            }
+            let mut event = recorded.event;
+            set_timestamp(&mut event, self.started + recorded.time);
+            events.push(event);
+        }
+        self.frame += 1;
+        events
+    }
+}
+
+// events that come from the user's devices, the real ones are dropped while replaying and
+// only these are replayed
+pub fn is_user_input(event: &Event) -> bool {
+    encode(event).is_some() && !matches!(event, Event::Quit { .. } | Event::Window { .. })
+}
+
+fn set_timestamp(event: &mut Event, time: u64) {
+    match event {
+        Event::Quit { timestamp }
+        | Event::Window { timestamp, .. }
+        | Event::KeyDown { timestamp, .. }
+        | Event::KeyUp { timestamp, .. }
+        | Event::TextEditing { timestamp, .. }
+        | Event::TextInput { timestamp, .. }
+        | Event::MouseMotion { timestamp, .. }
+        | Event::MouseButtonDown { timestamp, .. }
+        | Event::MouseButtonUp { timestamp, .. }
+        | Event::MouseWheel { timestamp, .. }
+        | Event::ControllerAxisMotion { timestamp, .. }
+        | Event::ControllerButtonDown { timestamp, .. }
+        | Event::ControllerButtonUp { timestamp, .. }
+        | Event::FingerDown { timestamp, .. }
+        | Event::FingerUp { timestamp, .. }
+        | Event::FingerMotion { timestamp, .. }
+        | Event::DropBegin { timestamp, .. }
+        | Event::DropFile { timestamp, .. }
+        | Event::DropText { timestamp, .. }
+        | Event::DropComplete { timestamp, .. }
+        | Event::PenProximityIn { timestamp, .. }
+        | Event::PenProximityOut { timestamp, .. }
+        | Event::PenDown { timestamp, .. }
+        | Event::PenUp { timestamp, .. }
+        | Event::PenMotion { timestamp, .. }
+        | Event::PenButtonDown { timestamp, .. }
+        | Event::PenButtonUp { timestamp, .. }
+        | Event::PenAxis { timestamp, .. } => *timestamp = time,
+        _ => {}
+    }
+}
+
+// everything but the timestamp and window, None for events that aren't recorded
+fn encode(event: &Event) -> Option<String> {
+    let key_fields = |keycode: &Option<Keycode>,
+                      scancode: &Option<Scancode>,
+                      keymod: &Mod,
+                      repeat: &bool,
+                      which: &u32,
+                      raw: &u16| {
+        format!(
+            "{} {} {} {} {} {}",
+            optional(keycode.map(|key| key.to_ll())),
+            optional(scancode.map(|scancode| scancode.to_i32())),
+            keymod.bits(),
+            repeat,
+            which,
+            raw
+        )
+    };
+    let finger_fields =
+        |touch_id: &u64, finger_id: &u64, x: &f32, y: &f32, dx: &f32, dy: &f32, pressure: &f32| {
+            format!(
+                "{} {} {} {} {} {} {}",
+                touch_id, finger_id, x, y, dx, dy, pressure
+            )
+        };
+
+    Some(match event {
+        Event::Quit { .. } => "Quit".to_string(),
+        Event::Window { win_event, .. } => {
+            let (name, data1, data2) = window_event_parts(win_event);
+            format!("Window {} {} {}", name, data1, data2)
+        }
+        Event::KeyDown {
+            keycode,
+            scancode,
+            keymod,
+            repeat,
+            which,
+            raw,
+            ..
+        } => format!(
+            "KeyDown {}",
+            key_fields(keycode, scancode, keymod, repeat, which, raw)
+        ),
+        Event::KeyUp {
+            keycode,
+            scancode,
+            keymod,
+            repeat,
+            which,
+            raw,
+            ..
+        } => format!(
+            "KeyUp {}",
+            key_fields(keycode, scancode, keymod, repeat, which, raw)
+        ),
+        Event::TextEditing {
+            text,
+            start,
+            length,
+            ..
+        } => format!("TextEditing {} {} {}", start, length, escape(text)),
+        Event::TextInput { text, .. } => format!("TextInput {}", escape(text)),
+        Event::MouseMotion {
+            which,
+            mousestate,
+            x,
+            y,
+            xrel,
+            yrel,
+            ..
+        } => format!(
+            "MouseMotion {} {} {} {} {} {}",
+            which,
+            mousestate.to_sdl_state(),
+            x,
+            y,
+            xrel,
+            yrel
+        ),
+        Event::MouseButtonDown {
+            which,
+            mouse_btn,
+            clicks,
+            x,
+            y,
+            ..
+        } => format!(
+            "MouseButtonDown {} {} {} {} {}",
+            which, *mouse_btn as u8, clicks, x, y
+        ),
+        Event::MouseButtonUp {
+            which,
+            mouse_btn,
+            clicks,
+            x,
+            y,
+            ..
+        } => format!(
+            "MouseButtonUp {} {} {} {} {}",
+            which, *mouse_btn as u8, clicks, x, y
+        ),
+        Event::MouseWheel {
+            which,
+            x,
+            y,
+            direction,
+            mouse_x,
+            mouse_y,
+            ..
+        } => format!(
+            "MouseWheel {} {} {} {} {} {}",
+            which,
+            x,
+            y,
+            direction.to_ll(),
+            mouse_x,
+            mouse_y
+        ),
+        Event::ControllerAxisMotion {
+            which, axis, value, ..
+        } => format!("ControllerAxisMotion {} {} {}", which, axis.string(), value),
+        Event::ControllerButtonDown { which, button, .. } => {
+            format!("ControllerButtonDown {} {}", which, button.string())
+        }
+        Event::ControllerButtonUp { which, button, .. } => {
+            format!("ControllerButtonUp {} {}", which, button.string())
+        }
+        Event::FingerDown {
+            touch_id,
+            finger_id,
+            x,
+            y,
+            dx,
+            dy,
+            pressure,
+            ..
+        } => format!(
+            "FingerDown {}",
+            finger_fields(touch_id, finger_id, x, y, dx, dy, pressure)
+        ),
+        Event::FingerUp {
+            touch_id,
+            finger_id,
+            x,
+            y,
+            dx,
+            dy,
+            pressure,
+            ..
+        } => format!(
+            "FingerUp {}",
+            finger_fields(touch_id, finger_id, x, y, dx, dy, pressure)
+        ),
+        Event::FingerMotion {
+            touch_id,
+            finger_id,
+            x,
+            y,
+            dx,
+            dy,
+            pressure,
+            ..
+        } => format!(
+            "FingerMotion {}",
+            finger_fields(touch_id, finger_id, x, y, dx, dy, pressure)
+        ),
+        Event::DropBegin { .. } => "DropBegin".to_string(),
+        Event::DropFile { filename, .. } => format!("DropFile {}", escape(filename)),
+        Event::DropText { filename, .. } => format!("DropText {}", escape(filename)),
+        Event::DropComplete { .. } => "DropComplete".to_string(),
+        Event::PenProximityIn { which, .. } => format!("PenProximityIn {}", which),
+        Event::PenProximityOut { which, .. } => format!("PenProximityOut {}", which),
+        Event::PenDown {
+            which,
+            x,
+            y,
+            eraser,
+            ..
+        } => format!("PenDown {} {} {} {}", which, x, y, eraser),
+        Event::PenUp {
+            which,
+            x,
+            y,
+            eraser,
+            ..
+        } => format!("PenUp {} {} {} {}", which, x, y, eraser),
+        Event::PenMotion { which, x, y, .. } => format!("PenMotion {} {} {}", which, x, y),
+        Event::PenButtonDown {
+            which,
+            x,
+            y,
+            button,
+            ..
+        } => format!("PenButtonDown {} {} {} {}", which, x, y, button),
+        Event::PenButtonUp {
+            which,
+            x,
+            y,
+            button,
+            ..
+        } => format!("PenButtonUp {} {} {} {}", which, x, y, button),
+        Event::PenAxis {
+            which,
+            x,
+            y,
+            axis,
+            value,
+            ..
+        } => format!("PenAxis {} {} {} {} {}", which, x, y, *axis as i32, value),
+        _ => return None,
+    })
+}
+
+fn event_line(recorded: &RecordedEvent) -> String {
+    let Some(fields) = encode(&recorded.event) else {
+        return String::new();
+    };
+    let window = match recorded.window_id {
+        Some(id) => id.to_string(),
+        None => "-".to_string(),
+    };
+    format!(
+        "{} {} {} {}\n",
+        recorded.frame, recorded.time, window, fields
+    )
+}
+
+// None if the line is an event instead
+fn parse_frame_line(line: &str) -> Option<anyhow::Result<(u64, RecordedFrame)>> {
+    let (frame, rest) = line.split_once(' ')?;
+    let mut fields = Fields {
+        rest: rest.strip_prefix("Frame ")?,
+    };
+    let frame = frame
+        .parse()
+        .map_err(|_| anyhow!("Invalid field \"{}\"", frame));
+    Some(frame.and_then(|frame| {
+        let timing = RecordedFrame {
+            dt: fields.next()?,
+            ticks: fields.next()?,
+        };
+        Ok((frame, timing))
+    }))
+}
+
+fn parse_line(line: &str) -> anyhow::Result<RecordedEvent> {
+    let mut fields = Fields { rest: line };
+    let frame = fields.next()?;
+    let time = fields.next()?;
+    let window_id = fields.optional()?;
+    // events without a window of their own are written with "-", the field is unused then
+    let window = window_id.unwrap_or(0);
+    let timestamp = time;
+
+    let kind = fields.token()?;
+    let event = match kind {
+        "Quit" => Event::Quit { timestamp },
+        "Window" => {
+            let name = fields.token()?;
+            let win_event = window_event_from_parts(name, fields.next()?, fields.next()?)
+                .ok_or(anyhow!("Unknown window event \"{}\"", name))?;
+            Event::Window {
+                timestamp,
+                window_id: window,
+                win_event,
+            }
+        }
+        "KeyDown" | "KeyUp" => {
+            let keycode = fields.optional::<u32>()?;
+            let scancode = fields.optional::<i32>()?;
+            let keycode = keycode.and_then(|key| Keycode::from_i32(key as i32));
+            let scancode = scancode.and_then(Scancode::from_i32);
+            let keymod = Mod::from_bits_truncate(fields.next()?);
+            let repeat = fields.next()?;
+            let which = fields.next()?;
+            let raw = fields.next()?;
+            if kind == "KeyDown" {
+                Event::KeyDown {
+                    timestamp,
+                    window_id: window,
+                    keycode,
+                    scancode,
+                    keymod,
+                    repeat,
+                    which,
+                    raw,
+                }
+            } else {
+                Event::KeyUp {
+                    timestamp,
+                    window_id: window,
+                    keycode,
+                    scancode,
+                    keymod,
+                    repeat,
+                    which,
+                    raw,
+                }
+            }
+        }
+        "TextEditing" => Event::TextEditing {
+            timestamp,
+            window_id: window,
+            start: fields.next()?,
+            length: fields.next()?,
+            text: fields.text(),
+        },
+        "TextInput" => Event::TextInput {
+            timestamp,
+            window_id: window,
+            text: fields.text(),
+        },
+        "MouseMotion" => Event::MouseMotion {
+            timestamp,
+            window_id: window,
+            which: fields.next()?,
+            mousestate: MouseState::from_sdl_state(fields.next()?),
+            x: fields.next()?,
+            y: fields.next()?,
+            xrel: fields.next()?,
+            yrel: fields.next()?,
+        },
+        "MouseButtonDown" | "MouseButtonUp" => {
+            let which = fields.next()?;
+            let mouse_btn = MouseButton::from_ll(fields.next()?);
+            let clicks = fields.next()?;
+            let (x, y) = (fields.next()?, fields.next()?);
+            if kind == "MouseButtonDown" {
+                Event::MouseButtonDown {
+                    timestamp,
+                    window_id: window,
+                    which,
+                    mouse_btn,
+                    clicks,
+                    x,
+                    y,
+                }
+            } else {
+                Event::MouseButtonUp {
+                    timestamp,
+                    window_id: window,
+                    which,
+                    mouse_btn,
+                    clicks,
+                    x,
+                    y,
+                }
+            }
+        }
+        "MouseWheel" => Event::MouseWheel {
+            timestamp,
+            window_id: window,
+            which: fields.next()?,
+            x: fields.next()?,
+            y: fields.next()?,
+            direction: MouseWheelDirection::from_ll(fields.next()?),
+            mouse_x: fields.next()?,
+            mouse_y: fields.next()?,
+        },
+        "ControllerAxisMotion" => {
+            let which = fields.next()?;
+            let name = fields.token()?;
+            let axis =
+                GamepadAxis::from_string(name).ok_or(anyhow!("Unknown axis \"{}\"", name))?;
+            Event::ControllerAxisMotion {
+                timestamp,
+                which,
+                axis,
+                value: fields.next()?,
+            }
+        }
+        "ControllerButtonDown" | "ControllerButtonUp" => {
+            let which = fields.next()?;
+            let name = fields.token()?;
+            let button =
+                GamepadButton::from_string(name).ok_or(anyhow!("Unknown button \"{}\"", name))?;
+            if kind == "ControllerButtonDown" {
+                Event::ControllerButtonDown {
+                    timestamp,
+                    which,
+                    button,
+                }
+            } else {
+                Event::ControllerButtonUp {
+                    timestamp,
+                    which,
+                    button,
+                }
+            }
+        }
+        "FingerDown" | "FingerUp" | "FingerMotion" => {
+            let touch_id = fields.next()?;
+            let finger_id = fields.next()?;
+            let (x, y) = (fields.next()?, fields.next()?);
+            let (dx, dy) = (fields.next()?, fields.next()?);
+            let pressure = fields.next()?;
+            match kind {
+                "FingerDown" => Event::FingerDown {
+                    timestamp,
+                    touch_id,
+                    finger_id,
+                    x,
+                    y,
+                    dx,
+                    dy,
+                    pressure,
+                },
+                "FingerUp" => Event::FingerUp {
+                    timestamp,
+                    touch_id,
+                    finger_id,
+                    x,
+                    y,
+                    dx,
+                    dy,
+                    pressure,
+                },
+                _ => Event::FingerMotion {
+                    timestamp,
+                    touch_id,
+                    finger_id,
+                    x,
+                    y,
+                    dx,
+                    dy,
+                    pressure,
+                },
+            }
+        }
+        "DropBegin" => Event::DropBegin {
+            timestamp,
+            window_id: window,
+        },
+        "DropFile" => Event::DropFile {
+            timestamp,
+            window_id: window,
+            filename: fields.text(),
+        },
+        "DropText" => Event::DropText {
+            timestamp,
+            window_id: window,
+            filename: fields.text(),
+        },
+        "DropComplete" => Event::DropComplete {
+            timestamp,
+            window_id: window,
+        },
+        "PenProximityIn" => Event::PenProximityIn {
+            timestamp,
+            which: fields.next()?,
+            window,
+        },
+        "PenProximityOut" => Event::PenProximityOut {
+            timestamp,
+            which: fields.next()?,
+            window,
+        },
+        "PenDown" | "PenUp" => {
+            let which = fields.next()?;
+            let (x, y) = (fields.next()?, fields.next()?);
+            let eraser = fields.next()?;
+            if kind == "PenDown" {
+                Event::PenDown {
+                    timestamp,
+                    which,
+                    window,
+                    x,
+                    y,
+                    eraser,
+                }
+            } else {
+                Event::PenUp {
+                    timestamp,
+                    which,
+                    window,
+                    x,
+                    y,
+                    eraser,
+                }
+            }
+        }
+        "PenMotion" => Event::PenMotion {
+            timestamp,
+            which: fields.next()?,
+            window,
+            x: fields.next()?,
+            y: fields.next()?,
+        },
+        "PenButtonDown" | "PenButtonUp" => {
+            let which = fields.next()?;
+            let (x, y) = (fields.next()?, fields.next()?);
+            let button = fields.next()?;
+            if kind == "PenButtonDown" {
+                Event::PenButtonDown {
+                    timestamp,
+                    which,
+                    window,
+                    x,
+                    y,
+                    button,
+                }
+            } else {
+                Event::PenButtonUp {
+                    timestamp,
+                    which,
+                    window,
+                    x,
+                    y,
+                    button,
+                }
+            }
+        }
+        "PenAxis" => Event::PenAxis {
+            timestamp,
+            which: fields.next()?,
+            window,
+            x: fields.next()?,
+            y: fields.next()?,
+            axis: PenAxis::from_ll(SDL_PenAxis(fields.next()?)),
+            value: fields.next()?,
+        },
+        _ => return Err(anyhow!("Unknown event \"{}\"", kind)),
+    };
+
+    Ok(RecordedEvent {
+        frame,
+        time,
+        window_id,
+        event,
+    })
+}
+
+// fields are separated by exactly one space so text at the end keeps its own spaces
+struct Fields<'a> {
+    rest: &'a str,
+}
+impl<'a> Fields<'a> {
+    fn token(&mut self) -> anyhow::Result<&'a str> {
+        if self.rest.is_empty() {
+            return Err(anyhow!("Missing field"));
+        }
+        let (token, rest) = self.rest.split_once(' ').unwrap_or((self.rest, ""));
+        self.rest = rest;
+        Ok(token)
+    }
+
+    fn next<T: FromStr>(&mut self) -> anyhow::Result<T> {
+        let token = self.token()?;
+        token
+            .parse()
+            .map_err(|_| anyhow!("Invalid field \"{}\"", token))
+    }
+
+    // "-" for None
+    fn optional<T: FromStr>(&mut self) -> anyhow::Result<Option<T>> {
+        match self.token()? {
+            "-" => Ok(None),
+            token => token
+                .parse()
+                .map(Some)
+                .map_err(|_| anyhow!("Invalid field \"{}\"", token)),
+        }
+    }
+
+    fn text(&mut self) -> String {
+        unescape(std::mem::take(&mut self.rest))
+    }
+}
+
+fn optional(value: Option<impl ToString>) -> String {
+    value.map_or("-".to_string(), |value| value.to_string())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(other) => unescaped.push(other),
+            None => {}
+        }
+    }
+    unescaped
+}
+
+fn window_event_parts(event: &WindowEvent) -> (&'static str, i32, i32) {
+    match *event {
+        WindowEvent::None => ("None", 0, 0),
+        WindowEvent::Shown => ("Shown", 0, 0),
+        WindowEvent::Hidden => ("Hidden", 0, 0),
+        WindowEvent::Exposed => ("Exposed", 0, 0),
+        WindowEvent::Moved(x, y) => ("Moved", x, y),
+        WindowEvent::Resized(w, h) => ("Resized", w, h),
+        WindowEvent::PixelSizeChanged(w, h) => ("PixelSizeChanged", w, h),
+        WindowEvent::Minimized => ("Minimized", 0, 0),
+        WindowEvent::Maximized => ("Maximized", 0, 0),
+        WindowEvent::Restored => ("Restored", 0, 0),
+        WindowEvent::MouseEnter => ("MouseEnter", 0, 0),
+        WindowEvent::MouseLeave => ("MouseLeave", 0, 0),
+        WindowEvent::FocusGained => ("FocusGained", 0, 0),
+        WindowEvent::FocusLost => ("FocusLost", 0, 0),
+        WindowEvent::CloseRequested => ("CloseRequested", 0, 0),
+        WindowEvent::HitTest(x, y) => ("HitTest", x, y),
+        WindowEvent::ICCProfChanged => ("ICCProfChanged", 0, 0),
+        WindowEvent::DisplayChanged(display) => ("DisplayChanged", display, 0),
+    }
+}
+
+fn window_event_from_parts(name: &str, data1: i32, data2: i32) -> Option<WindowEvent> {
+    Some(match name {
+        "None" => WindowEvent::None,
+        "Shown" => WindowEvent::Shown,
+        "Hidden" => WindowEvent::Hidden,
+        "Exposed" => WindowEvent::Exposed,
+        "Moved" => WindowEvent::Moved(data1, data2),
+        "Resized" => WindowEvent::Resized(data1, data2),
+        "PixelSizeChanged" => WindowEvent::PixelSizeChanged(data1, data2),
+        "Minimized" => WindowEvent::Minimized,
+        "Maximized" => WindowEvent::Maximized,
+        "Restored" => WindowEvent::Restored,
+        "MouseEnter" => WindowEvent::MouseEnter,
+        "MouseLeave" => WindowEvent::MouseLeave,
+        "FocusGained" => WindowEvent::FocusGained,
+        "FocusLost" => WindowEvent::FocusLost,
+        "CloseRequested" => WindowEvent::CloseRequested,
+        "HitTest" => WindowEvent::HitTest(data1, data2),
+        "ICCProfChanged" => WindowEvent::ICCProfChanged,
+        "DisplayChanged" => WindowEvent::DisplayChanged(data1),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_round_trip() {
+        let events = vec![
+            Event::KeyDown {
+                timestamp: 0,
+                window_id: 1,
+                keycode: Some(Keycode::A),
+                scancode: Some(Scancode::A),
+                keymod: Mod::LCTRLMOD | Mod::NUMMOD,
+                repeat: false,
+                which: 0,
+                raw: 4,
+            },
+            Event::TextInput {
+                timestamp: 0,
+                window_id: 1,
+                text: "two  spaces\\ and\na newline ".to_string(),
+            },
+            Event::MouseMotion {
+                timestamp: 0,
+                window_id: 1,
+                which: 0,
+                mousestate: MouseState::from_sdl_state(1),
+                x: 10.5,
+                y: 0.1,
+                xrel: -1.25,
+                yrel: 3.0,
+            },
+            Event::Window {
+                timestamp: 0,
+                window_id: 1,
+                win_event: WindowEvent::Resized(800, 600),
+            },
+            Event::ControllerAxisMotion {
+                timestamp: 0,
+                which: 3,
+                axis: GamepadAxis::LeftX,
+                value: -32768,
+            },
+            Event::Quit { timestamp: 0 },
+        ];
+        let recording = InputRecording {
+            events: events
+                .into_iter()
+                .enumerate()
+                .map(|(i, event)| RecordedEvent {
+                    frame: i as u64 / 2,
+                    time: i as u64 * 1_000_000,
+                    window_id: SQ::get_window_id(&event),
+                    event,
+                })
+                .collect(),
+            frames: vec![
+                RecordedFrame {
+                    dt: 1.0 / 60.0,
+                    ticks: 1,
+                },
+                RecordedFrame { dt: 0.1, ticks: 0 },
+            ],
+        };
+
+        let text = recording.to_text();
+        let parsed = InputRecording::parse(&text).unwrap();
+        assert_eq!(parsed.to_text(), text);
+        assert_eq!(parsed.frames, recording.frames);
+        assert_eq!(parsed.events.len(), recording.events.len());
+        for (parsed, recorded) in parsed.events.iter().zip(&recording.events) {
+            assert_eq!(parsed.frame, recorded.frame);
+            assert_eq!(parsed.time, recorded.time);
+            assert_eq!(parsed.window_id, recorded.window_id);
+        }
+        match &parsed.events[1].event {
+            Event::TextInput { text, .. } => assert_eq!(text, "two  spaces\\ and\na newline "),
+            event => panic!("{:?}", event),
+        }
+    }
+
+    #[test]
+    fn replay_skips_window_events() {
+        let text = "0 0 1 Window Resized 800 600\n0 5 1 TextInput a\n0 Frame 0.5 2\n";
+        let mut replay = InputReplay::new(InputRecording::parse(text).unwrap());
+        assert_eq!(replay.frame(), Some(RecordedFrame { dt: 0.5, ticks: 2 }));
+        let events = replay.next_frame();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], Event::TextInput { .. }));
+        assert!(replay.is_finished());
+    }
+}
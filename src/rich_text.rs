@@ -0,0 +1,750 @@
+use crate::*;
+use std::{collections::HashMap, ops::Range};
+
+use cgmath::{Vector2, Vector4};
+use slotmap::SlotMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanStyle {
+    // None for the paragraph's fonts, sizes and weights are separate fonts, e.g. a bold file
+    // loaded at 24.0 for a heading
+    pub fonts: Option<Vec<FontKey>>,
+    pub color: Vector4<f32>,
+    pub underline: bool,
+    pub strikethrough: bool,
+    pub link: Option<String>,
+}
+impl SpanStyle {
+    pub fn new() -> Self {
+        Self {
+            fonts: None,
+            color: Vector4::new(1.0, 1.0, 1.0, 1.0),
+            underline: false,
+            strikethrough: false,
+            link: None,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub enum SpanContent {
+    Text(String),
+    // sits on the baseline like a glyph as tall as size.y
+    Image {
+        texture: TextureKey,
+        uv_rect: Rectangle<f32>,
+        size: Vector2<f32>,
+    },
+}
+
+#[derive(Clone, PartialEq)]
+pub struct TextSpan {
+    pub content: SpanContent,
+    pub style: SpanStyle,
+}
+impl TextSpan {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            content: SpanContent::Text(text.into()),
+            style: SpanStyle::new(),
+        }
+    }
+
+    pub fn image(texture: TextureKey, size: Vector2<f32>) -> Self {
+        Self {
+            content: SpanContent::Image {
+                texture,
+                uv_rect: Rectangle {
+                    x: 0.0,
+                    y: 0.0,
+                    width: 1.0,
+                    height: 1.0,
+                },
+                size,
+            },
+            style: SpanStyle::new(),
+        }
+    }
+
+    pub fn with_style(mut self, style: SpanStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn with_fonts(mut self, fonts: Vec<FontKey>) -> Self {
+        self.style.fonts = Some(fonts);
+        self
+    }
+
+    pub fn with_color(mut self, color: Vector4<f32>) -> Self {
+        self.style.color = color;
+        self
+    }
+
+    pub fn with_underline(mut self, underline: bool) -> Self {
+        self.style.underline = underline;
+        self
+    }
+
+    pub fn with_strikethrough(mut self, strikethrough: bool) -> Self {
+        self.style.strikethrough = strikethrough;
+        self
+    }
+
+    pub fn with_link(mut self, link: impl Into<String>) -> Self {
+        self.style.link = Some(link.into());
+        self
+    }
+}
+
+// what names in markup refer to
+pub struct MarkupContext {
+    // "bold", "italic" and "bold italic" for [b] and [i], any other name for [font=name], whose
+    // own bold and italic are "name bold" and so on
+    pub fonts: HashMap<String, Vec<FontKey>>,
+    pub images: HashMap<String, (TextureKey, Vector2<f32>)>, // for [img=name]
+    pub color: Vector4<f32>,
+    pub link_color: Vector4<f32>,
+}
+impl MarkupContext {
+    pub fn new() -> Self {
+        Self {
+            fonts: HashMap::new(),
+            images: HashMap::new(),
+            color: Vector4::new(1.0, 1.0, 1.0, 1.0),
+            link_color: Vector4::new(0.4, 0.6, 1.0, 1.0),
+        }
+    }
+
+    pub fn with_font(mut self, name: impl Into<String>, fonts: Vec<FontKey>) -> Self {
+        self.fonts.insert(name.into(), fonts);
+        self
+    }
+
+    pub fn with_image(
+        mut self,
+        name: impl Into<String>,
+        texture: TextureKey,
+        size: Vector2<f32>,
+    ) -> Self {
+        self.images.insert(name.into(), (texture, size));
+        self
+    }
+
+    pub fn with_color(mut self, color: Vector4<f32>) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_link_color(mut self, link_color: Vector4<f32>) -> Self {
+        self.link_color = link_color;
+        self
+    }
+}
+
+#[derive(Clone, PartialEq)]
+enum Tag {
+    Bold,
+    Italic,
+    Underline,
+    Strikethrough,
+    Color(Vector4<f32>),
+    Font(String),
+    Url(String),
+}
+impl Tag {
+    fn name(&self) -> &'static str {
+        match self {
+            Tag::Bold => "b",
+            Tag::Italic => "i",
+            Tag::Underline => "u",
+            Tag::Strikethrough => "s",
+            Tag::Color(_) => "color",
+            Tag::Font(_) => "font",
+            Tag::Url(_) => "url",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct RichText {
+    pub spans: Vec<TextSpan>,
+}
+impl RichText {
+    pub fn new() -> Self {
+        Self { spans: vec![] }
+    }
+
+    pub fn with_span(mut self, span: TextSpan) -> Self {
+        self.spans.push(span);
+        self
+    }
+
+    pub fn push(&mut self, span: TextSpan) {
+        self.spans.push(span);
+    }
+
+    // e.g. "[b]bold[/b], [color=#ff8000]orange[/color], [url=https://example.com]a link[/url]",
+    // also [i], [u], [s], [font=name] and [img=name], tags nest and [[ is a literal [, anything
+    // else in brackets shows as written, including closing tags for nothing that's open, a
+    // closing tag also closes the tags opened after its own, and the end of markup closes all
+    pub fn parse(markup: &str, context: &MarkupContext) -> Self {
+        let mut rich = Self::new();
+        let mut stack: Vec<Tag> = vec![];
+        let mut text = String::new();
+        let mut rest = markup;
+        while let Some(open) = rest.find('[') {
+            text.push_str(&rest[..open]);
+            rest = &rest[open..];
+            if let Some(after) = rest.strip_prefix("[[") {
+                text.push('[');
+                rest = after;
+                continue;
+            }
+            let parsed = rest.find(']').and_then(|close| {
+                let markup = parse_tag(&rest[1..close], &stack, context)?;
+                Some((markup, close))
+            });
+            let Some((markup, close)) = parsed else {
+                text.push('[');
+                rest = &rest[1..];
+                continue;
+            };
+            rest = &rest[close + 1..];
+
+            if !text.is_empty() {
+                let style = style_of(&stack, context);
+                rich.push(TextSpan::text(std::mem::take(&mut text)).with_style(style));
+            }
+            match markup {
+                Markup::Open(tag) => stack.push(tag),
+                Markup::Close(depth) => stack.truncate(depth),
+                Markup::Image(texture, size) => {
+                    let style = style_of(&stack, context);
+                    rich.push(TextSpan::image(texture, size).with_style(style));
+                }
+            }
+        }
+        text.push_str(rest);
+        if !text.is_empty() {
+            rich.push(TextSpan::text(text).with_style(style_of(&stack, context)));
+        }
+        rich
+    }
+
+    // text that parses back to itself
+    pub fn escape(text: &str) -> String {
+        text.replace('[', "[[")
+    }
+
+    // the text with each image as U+FFFC, and the range each span takes up in it
+    pub fn to_plain(&self) -> (String, Vec<Range<usize>>) {
+        let mut text = String::new();
+        let mut ranges = vec![];
+        for span in &self.spans {
+            let start = text.len();
+            match &span.content {
+                SpanContent::Text(content) => text.push_str(content),
+                SpanContent::Image { .. } => text.push('\u{FFFC}'),
+            }
+            ranges.push(start..text.len());
+        }
+        (text, ranges)
+    }
+}
+
+enum Markup {
+    Open(Tag),
+    Close(usize),                    // the depth of the stack it closes down to
+    Image(TextureKey, Vector2<f32>), // images have no closing tag
+}
+
+// None for anything that isn't a tag
+fn parse_tag(tag: &str, stack: &[Tag], context: &MarkupContext) -> Option<Markup> {
+    if let Some(name) = tag.strip_prefix('/') {
+        return stack
+            .iter()
+            .rposition(|open| open.name() == name)
+            .map(Markup::Close);
+    }
+    let (name, value) = match tag.split_once('=') {
+        Some((name, value)) => (name, Some(value)),
+        None => (tag, None),
+    };
+    let tag = match (name, value) {
+        ("b", None) => Tag::Bold,
+        ("i", None) => Tag::Italic,
+        ("u", None) => Tag::Underline,
+        ("s", None) => Tag::Strikethrough,
+        ("color", Some(value)) => Tag::Color(parse_color(value)?),
+        ("font", Some(value)) if context.fonts.contains_key(value) => Tag::Font(value.to_string()),
+        ("url", Some(value)) => Tag::Url(value.to_string()),
+        ("img", Some(value)) => {
+            let (texture, size) = context.images.get(value)?;
+            return Some(Markup::Image(*texture, *size));
+        }
+        _ => return None,
+    };
+    Some(Markup::Open(tag))
+}
+
+fn style_of(stack: &[Tag], context: &MarkupContext) -> SpanStyle {
+    let mut style = SpanStyle::new();
+    style.color = context.color;
+    let (mut bold, mut italic, mut font) = (false, false, None);
+    // inner tags override outer ones
+    for tag in stack {
+        match tag {
+            Tag::Bold => bold = true,
+            Tag::Italic => italic = true,
+            Tag::Underline => style.underline = true,
+            Tag::Strikethrough => style.strikethrough = true,
+            Tag::Color(color) => style.color = *color,
+            Tag::Font(name) => font = Some(name.as_str()),
+            Tag::Url(url) => {
+                style.link = Some(url.clone());
+                style.underline = true;
+                style.color = context.link_color;
+            }
+        }
+    }
+
+    let variant = match (bold, italic) {
+        (true, true) => "bold italic",
+        (true, false) => "bold",
+        (false, true) => "italic",
+        (false, false) => "",
+    };
+    let name = match (font, variant) {
+        (Some(font), "") => font.to_string(),
+        (Some(font), variant) => format!("{} {}", font, variant),
+        (None, variant) => variant.to_string(),
+    };
+    // a missing variant keeps the family's regular font
+    style.fonts = context
+        .fonts
+        .get(&name)
+        .or_else(|| font.and_then(|font| context.fonts.get(font)))
+        .cloned();
+    style
+}
+
+// "#rrggbb" or "#rrggbbaa"
+fn parse_color(value: &str) -> Option<Vector4<f32>> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    if !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) || !(hex.len() == 6 || hex.len() == 8) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap() as f32 / 255.0;
+    let alpha = if hex.len() == 8 { channel(6) } else { 1.0 };
+    Some(Vector4::new(channel(0), channel(2), channel(4), alpha))
+}
+
+// underlines and strikethroughs, drawn as tinted rectangles
+#[derive(Clone, Copy, PartialEq)]
+pub struct RichDecoration {
+    pub bounds: Rectangle<f32>,
+    pub color: Vector4<f32>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct RichImage {
+    pub texture: TextureKey,
+    pub uv_rect: Rectangle<f32>,
+    pub bounds: Rectangle<f32>,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct RichLink {
+    pub url: String,
+    pub bounds: Rectangle<f32>, // one per line a wrapped link is on
+}
+
+// everything is relative to the paragraph's top left corner
+pub struct RichParagraph {
+    pub paragraph: Paragraph,
+    pub spans: Vec<(Range<usize>, SpanStyle)>, // byte ranges of paragraph.text
+    pub images: Vec<RichImage>,
+    pub decorations: Vec<RichDecoration>,
+    pub links: Vec<RichLink>,
+}
+impl RichParagraph {
+    pub fn layout(
+        fonts: &mut SlotMap<FontKey, SQFont>,
+        textures: &mut SlotMap<TextureKey, SQTexture>,
+        render_context: &RenderContext,
+        rich: &RichText,
+        style: &ParagraphStyle,
+    ) -> Self {
+        let (text, ranges) = rich.to_plain();
+        let runs: Vec<StyleRun> = rich
+            .spans
+            .iter()
+            .zip(&ranges)
+            .map(|(span, range)| StyleRun {
+                range: range.clone(),
+                fonts: span
+                    .style
+                    .fonts
+                    .clone()
+                    .unwrap_or_else(|| style.fonts.clone()),
+                object: match span.content {
+                    SpanContent::Image { size, .. } => Some(size),
+                    SpanContent::Text(_) => None,
+                },
+            })
+            .collect();
+        let paragraph =
+            Paragraph::layout_runs(fonts, textures, render_context, &text, &runs, style);
+
+        let mut images = vec![];
+        for object in &paragraph.objects {
+            // empty spans start at the same index
+            let image = rich
+                .spans
+                .iter()
+                .zip(&ranges)
+                .filter(|(_, range)| range.contains(&object.index))
+                .find_map(|(span, _)| match span.content {
+                    SpanContent::Image {
+                        texture, uv_rect, ..
+                    } => Some((texture, uv_rect)),
+                    SpanContent::Text(_) => None,
+                });
+            if let Some((texture, uv_rect)) = image {
+                images.push(RichImage {
+                    texture,
+                    uv_rect,
+                    bounds: object.bounds,
+                });
+            }
+        }
+
+        let mut decorations = vec![];
+        let mut links = vec![];
+        for (span, range) in rich.spans.iter().zip(&ranges) {
+            let metrics = span
+                .style
+                .fonts
+                .as_ref()
+                .unwrap_or(&style.fonts)
+                .first()
+                .and_then(|key| fonts.get(*key))
+                .map(|font| font.metrics);
+            let rects = paragraph.line_rects(range.clone());
+            if let Some(metrics) = metrics {
+                let thickness = ((metrics.ascent + metrics.descent) / 16.0).max(1.0);
+                // just under the baseline, and through the middle of lowercase letters
+                let mut offsets = vec![];
+                if span.style.underline {
+                    offsets.push(metrics.descent / 3.0);
+                }
+                if span.style.strikethrough {
+                    offsets.push(-metrics.ascent / 3.0);
+                }
+                for offset in offsets {
+                    for (line, rect) in &rects {
+                        let y = paragraph.lines[*line].baseline + offset;
+                        decorations.push(RichDecoration {
+                            bounds: Rectangle {
+                                x: rect.x,
+                                y: y - thickness / 2.0,
+                                width: rect.width,
+                                height: thickness,
+                            },
+                            color: span.style.color,
+                        });
+                    }
+                }
+            }
+            if let Some(url) = &span.style.link {
+                links.extend(rects.iter().map(|(_, bounds)| RichLink {
+                    url: url.clone(),
+                    bounds: *bounds,
+                }));
+            }
+        }
+
+        Self {
+            paragraph,
+            spans: rich
+                .spans
+                .iter()
+                .zip(ranges)
+                .map(|(span, range)| (range, span.style.clone()))
+                .collect(),
+            images,
+            decorations,
+            links,
+        }
+    }
+
+    pub fn span_at(&self, index: usize) -> Option<&SpanStyle> {
+        self.spans
+            .iter()
+            .find(|(range, _)| range.contains(&index))
+            .map(|(_, style)| style)
+    }
+
+    pub fn link_at(&self, point: Vector2<f32>) -> Option<&str> {
+        self.links
+            .iter()
+            .find(|link| link.bounds.contains(point))
+            .map(|link| link.url.as_str())
+    }
+
+    // the paragraph's glyphs grouped by font and color, one Renderer::queue_glyphs call each
+    pub fn glyph_runs(&self) -> Vec<(FontKey, Vector4<f32>, Vec<PositionedGlyph>)> {
+        let mut runs: Vec<(FontKey, Vector4<f32>, Vec<PositionedGlyph>)> = vec![];
+        for glyph in &self.paragraph.glyphs {
+            // an ellipsis sits where the text was cut, it takes the color of what it replaced
+            let color = self
+                .span_at(glyph.index)
+                .or_else(|| self.span_at(glyph.index.saturating_sub(1)))
+                .map_or(Vector4::new(1.0, 1.0, 1.0, 1.0), |style| style.color);
+            match runs
+                .iter_mut()
+                .find(|(font, run_color, _)| *font == glyph.font && *run_color == color)
+            {
+                Some((_, _, glyphs)) => glyphs.push(glyph.positioned),
+                None => runs.push((glyph.font, color, vec![glyph.positioned])),
+            }
+        }
+        runs
+    }
+}
+
+pub struct RichTextMaterials {
+    pub fonts: HashMap<FontKey, Vec<MaterialKey>>, // AssetManager::font_materials for each font
+    pub sdf_styles: HashMap<FontKey, SdfStyle>,    // for fonts drawn with SDF_SHADER
+    pub images: HashMap<TextureKey, MaterialKey>,
+    pub decoration: Option<MaterialKey>, // samples a white texture, tinted per decoration
+}
+impl RichTextMaterials {
+    pub fn new() -> Self {
+        Self {
+            fonts: HashMap::new(),
+            sdf_styles: HashMap::new(),
+            images: HashMap::new(),
+            decoration: None,
+        }
+    }
+}
+
+impl Renderer {
+    // anything without a material in materials is left out
+    pub fn queue_rich_text(
+        &mut self,
+        paragraph: &RichParagraph,
+        materials: &RichTextMaterials,
+        origin: Vector2<f32>,
+        z_order: f32,
+        pass_name: RenderPassName,
+    ) {
+        let uniforms = |tint: Vector4<f32>| MaterialUniforms {
+            tint,
+            custom_params: vec![],
+        };
+        let sprite = |bounds: Rectangle<f32>| Transform::Sprite {
+            position: origin + bounds.min(),
+            rotation: 0.0,
+            scale: Vector2::new(bounds.width, bounds.height),
+            z_order,
+        };
+
+        for (font, color, glyphs) in paragraph.glyph_runs() {
+            let Some(font_materials) = materials.fonts.get(&font) else {
+                continue;
+            };
+            let uniforms = match materials.sdf_styles.get(&font) {
+                Some(sdf_style) => sdf_style.to_uniforms(color),
+                None => uniforms(color),
+            };
+            self.queue_glyphs(
+                &glyphs,
+                font_materials,
+                origin,
+                1.0,
+                z_order,
+                uniforms,
+                pass_name.clone(),
+            );
+        }
+
+        for image in &paragraph.images {
+            if let Some(material) = materials.images.get(&image.texture) {
+                self.queue(
+                    *material,
+                    Mapping::Sprite {
+                        uv_rect: image.uv_rect,
+                    },
+                    sprite(image.bounds),
+                    uniforms(Vector4::new(1.0, 1.0, 1.0, 1.0)),
+                    pass_name.clone(),
+                    true,
+                );
+            }
+        }
+
+        let Some(material) = materials.decoration else {
+            return;
+        };
+        for decoration in &paragraph.decorations {
+            self.queue(
+                material,
+                Mapping::Sprite {
+                    uv_rect: Rectangle {
+                        x: 0.0,
+                        y: 0.0,
+                        width: 1.0,
+                        height: 1.0,
+                    },
+                },
+                sprite(decoration.bounds),
+                uniforms(decoration.color),
+                pass_name.clone(),
+                true,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fonts {
+        regular: Vec<FontKey>,
+        bold: Vec<FontKey>,
+        italic: Vec<FontKey>,
+        bold_italic: Vec<FontKey>,
+    }
+
+    fn context() -> (MarkupContext, Fonts) {
+        let mut keys: SlotMap<FontKey, ()> = SlotMap::with_key();
+        let fonts = Fonts {
+            regular: vec![keys.insert(())],
+            bold: vec![keys.insert(())],
+            italic: vec![keys.insert(())],
+            bold_italic: vec![keys.insert(())],
+        };
+        let context = MarkupContext::new()
+            .with_font("serif", fonts.regular.clone())
+            .with_font("bold", fonts.bold.clone())
+            .with_font("italic", fonts.italic.clone())
+            .with_font("bold italic", fonts.bold_italic.clone());
+        (context, fonts)
+    }
+
+    // each span's text, an image as U+FFFC, with its style
+    fn spans(rich: &RichText) -> Vec<(String, SpanStyle)> {
+        let (text, ranges) = rich.to_plain();
+        ranges
+            .into_iter()
+            .zip(&rich.spans)
+            .map(|(range, span)| (text[range].to_string(), span.style.clone()))
+            .collect()
+    }
+
+    fn texts(rich: &RichText) -> Vec<String> {
+        spans(rich).into_iter().map(|(text, _)| text).collect()
+    }
+
+    #[test]
+    fn nested_tags() {
+        let (context, fonts) = context();
+        let rich = RichText::parse("a[b]b[i]bi[/i][u]bu[/u][/b]c", &context);
+        assert_eq!(texts(&rich), ["a", "b", "bi", "bu", "c"]);
+        let styles: Vec<SpanStyle> = spans(&rich).into_iter().map(|(_, style)| style).collect();
+        assert_eq!(styles[0], SpanStyle::new());
+        assert_eq!(styles[1].fonts, Some(fonts.bold.clone()));
+        assert_eq!(styles[2].fonts, Some(fonts.bold_italic));
+        assert_eq!(styles[3].fonts, Some(fonts.bold));
+        assert!(styles[3].underline);
+        assert_eq!(styles[4], SpanStyle::new());
+
+        // a family without a bold of its own keeps its regular font
+        let rich = RichText::parse("[font=serif][b]x[/b][/font]", &context);
+        assert_eq!(spans(&rich)[0].1.fonts, Some(fonts.regular));
+
+        let rich = RichText::parse("[url=https://example.com]link[/url]", &context);
+        let style = &spans(&rich)[0].1;
+        assert_eq!(style.link.as_deref(), Some("https://example.com"));
+        assert!(style.underline);
+        assert_eq!(style.color, context.link_color);
+    }
+
+    #[test]
+    fn mismatched_closing_tags() {
+        let (context, fonts) = context();
+        // closing an outer tag closes the ones inside it
+        let rich = RichText::parse("[b][i]x[/b]y[/i]", &context);
+        assert_eq!(texts(&rich), ["x", "y[/i]"]);
+        assert_eq!(spans(&rich)[0].1.fonts, Some(fonts.bold_italic));
+        assert_eq!(spans(&rich)[1].1, SpanStyle::new());
+
+        // closing tags for nothing that's open show as written
+        let rich = RichText::parse("[/u]z[s]s[/b]", &context);
+        assert_eq!(texts(&rich), ["[/u]z", "s[/b]"]);
+        // tags still open at the end close there
+        assert!(spans(&rich)[1].1.strikethrough);
+    }
+
+    #[test]
+    fn brackets_that_are_not_tags() {
+        let (context, _) = context();
+        let rich = RichText::parse("[[b]] and [[", &context);
+        assert_eq!(texts(&rich), ["[b]] and ["]);
+
+        let rich = RichText::parse("a [x] [font=missing]b [img=missing] [b", &context);
+        assert_eq!(texts(&rich), ["a [x] [font=missing]b [img=missing] [b"]);
+
+        // an unknown tag doesn't hide a real one after it
+        let rich = RichText::parse("[x [b]y[/b]", &context);
+        assert_eq!(texts(&rich), ["[x ", "y"]);
+
+        let plain = "[b]not bold[/b] [[ ] [";
+        let rich = RichText::parse(&RichText::escape(plain), &context);
+        assert_eq!(texts(&rich), [plain]);
+    }
+
+    #[test]
+    fn colors() {
+        let (context, _) = context();
+        let rich = RichText::parse(
+            "[color=#ff8000]o[/color][color=00000080]h[/color][color=red]r[/color]",
+            &context,
+        );
+        assert_eq!(texts(&rich), ["o", "h", "[color=red]r[/color]"]);
+        assert_eq!(
+            spans(&rich)[0].1.color,
+            Vector4::new(1.0, 128.0 / 255.0, 0.0, 1.0)
+        );
+        assert_eq!(
+            spans(&rich)[1].1.color,
+            Vector4::new(0.0, 0.0, 0.0, 128.0 / 255.0)
+        );
+
+        assert_eq!(parse_color("#+f+f+f"), None);
+        assert_eq!(parse_color("#fff"), None);
+        assert_eq!(
+            parse_color("#ffffff"),
+            Some(Vector4::new(1.0, 1.0, 1.0, 1.0))
+        );
+    }
+
+    #[test]
+    fn images() {
+        let (context, _) = context();
+        let mut textures: SlotMap<TextureKey, ()> = SlotMap::with_key();
+        let texture = textures.insert(());
+        let context = context.with_image("coin", texture, Vector2::new(16.0, 16.0));
+        let rich = RichText::parse("[b]x [img=coin][/b] y", &context);
+        assert_eq!(texts(&rich), ["x ", "\u{FFFC}", " y"]);
+        assert!(matches!(
+            rich.spans[1].content,
+            SpanContent::Image { texture: t, .. } if t == texture
+        ));
+        assert_eq!(rich.spans[1].style.fonts, spans(&rich)[0].1.fonts);
+    }
+}
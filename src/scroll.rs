@@ -0,0 +1,96 @@
+use crate::*;
+
+use cgmath::{InnerSpace, Vector2};
+
+// the offset of a scroll container, driven by InputManager::scroll and gliding to a stop
+// after a flick instead of halting with the last event
+pub struct KineticScroll {
+    pub offset: Vector2<f32>, // logical, grows to the right and down like a scrollbar
+    pub max_offset: Vector2<f32>, // content size minus viewport size
+    pub velocity: Vector2<f32>, // logical units per second
+    pub friction: f32,        // how quickly the velocity decays, per second
+    pub line_height: f32,     // logical units per wheel notch or SDL scroll unit
+    pub min_velocity: f32,    // slower than this stops dead
+}
+impl KineticScroll {
+    pub fn new() -> Self {
+        Self {
+            offset: Vector2::new(0.0, 0.0),
+            max_offset: Vector2::new(0.0, 0.0),
+            velocity: Vector2::new(0.0, 0.0),
+            friction: 6.0,
+            line_height: 40.0,
+            min_velocity: 5.0,
+        }
+    }
+
+    pub fn with_friction(mut self, friction: f32) -> Self {
+        self.friction = friction;
+        self
+    }
+
+    pub fn with_line_height(mut self, line_height: f32) -> Self {
+        self.line_height = line_height;
+        self
+    }
+
+    // call once per update with that update's scroll, dt in seconds
+    pub fn update(&mut self, scroll: &ScrollDelta, dt: f32) {
+        // SDL's y is positive away from the user, which moves the content back up
+        let to_offset = |delta: Vector2<f32>| Vector2::new(delta.x, -delta.y) * self.line_height;
+
+        let precise = to_offset(scroll.precise);
+        if precise != Vector2::new(0.0, 0.0) {
+            // the fingers are still on the trackpad, follow them and keep their speed for the glide
+            self.offset += precise;
+            if dt > 0.0 {
+                self.velocity = precise / dt;
+            }
+        } else {
+            // a notch travels exactly one line in total, the decay integrates to velocity / friction
+            self.velocity += to_offset(scroll.discrete) * self.friction;
+
+            let decay = (-self.friction * dt).exp();
+            self.offset += self.velocity * (1.0 - decay) / self.friction.max(f32::EPSILON);
+            self.velocity *= decay;
+        }
+
+        if self.velocity.magnitude() < self.min_velocity {
+            self.velocity = Vector2::new(0.0, 0.0);
+        }
+        self.clamp();
+    }
+
+    // e.g. the negated InputManager::mouse_velocity when a drag of the content is released,
+    // dragging the content down scrolls up
+    pub fn fling(&mut self, velocity: Vector2<f32>) {
+        self.velocity = velocity;
+    }
+
+    pub fn stop(&mut self) {
+        self.velocity = Vector2::new(0.0, 0.0);
+    }
+
+    pub fn is_moving(&self) -> bool {
+        self.velocity != Vector2::new(0.0, 0.0)
+    }
+
+    pub fn scroll_to(&mut self, offset: Vector2<f32>) {
+        self.offset = offset;
+        self.stop();
+        self.clamp();
+    }
+
+    // hitting an edge ends the glide along that axis
+    fn clamp(&mut self) {
+        let max = Vector2::new(self.max_offset.x.max(0.0), self.max_offset.y.max(0.0));
+        if self.offset.x < 0.0 || self.offset.x > max.x {
+            self.velocity.x = 0.0;
+        }
+        if self.offset.y < 0.0 || self.offset.y > max.y {
+            self.velocity.y = 0.0;
+        }
+        self.offset.x = self.offset.x.clamp(0.0, max.x);
+        self.offset.y = self.offset.y.clamp(0.0, max.y);
+    }
+}
@@ -0,0 +1,314 @@
+use crate::*;
+
+use cgmath::{Vector2, Vector4};
+use render::FilterMode;
+use wgpu::util::DeviceExt;
+
+// FreeType's default, how far past the outline in glyph pixels the distance field reaches, it
+// also bounds how wide outlines, glows and shadows can get before the glyph quad clips them
+pub const SDF_SPREAD: f32 = 8.0;
+
+// the source of assets/shaders/sdf.wgsl, for building the pipeline an SDF material points at
+pub const SDF_SHADER: &str = include_str!("../assets/shaders/sdf.wgsl");
+
+// bytes in SdfParams, the tint and the custom_params of SdfStyle::to_uniforms
+pub const SDF_PARAMS_SIZE: u64 = 24 * 4;
+
+// how sdf.wgsl draws a run, widths and offsets are in glyph pixels at the font's loaded size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SdfStyle {
+    pub weight: f32, // grows (positive) or thins (negative) the glyphs
+    pub outline_width: f32,
+    pub outline_color: Vector4<f32>,
+    pub glow_width: f32, // fades out beyond the outline
+    pub glow_color: Vector4<f32>,
+    pub shadow_offset: Vector2<f32>, // at most SDF_SPREAD either way, the padding between glyphs
+    pub shadow_softness: f32,
+    pub shadow_color: Vector4<f32>,
+}
+impl SdfStyle {
+    pub fn new() -> Self {
+        let clear = Vector4::new(0.0, 0.0, 0.0, 0.0);
+        Self {
+            weight: 0.0,
+            outline_width: 0.0,
+            outline_color: clear,
+            glow_width: 0.0,
+            glow_color: clear,
+            shadow_offset: Vector2::new(0.0, 0.0),
+            shadow_softness: 0.0,
+            shadow_color: clear,
+        }
+    }
+
+    pub fn with_weight(mut self, weight: f32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    pub fn with_outline(mut self, width: f32, color: Vector4<f32>) -> Self {
+        self.outline_width = width;
+        self.outline_color = color;
+        self
+    }
+
+    pub fn with_glow(mut self, width: f32, color: Vector4<f32>) -> Self {
+        self.glow_width = width;
+        self.glow_color = color;
+        self
+    }
+
+    pub fn with_shadow(mut self, offset: Vector2<f32>, softness: f32, color: Vector4<f32>) -> Self {
+        self.shadow_offset = offset;
+        self.shadow_softness = softness;
+        self.shadow_color = color;
+        self
+    }
+
+    // custom_params in the order of SdfParams in sdf.wgsl, which follows the tint
+    pub fn to_uniforms(&self, tint: Vector4<f32>) -> MaterialUniforms {
+        let mut custom_params = vec![];
+        for color in [self.outline_color, self.glow_color, self.shadow_color] {
+            custom_params.extend_from_slice(&[color.x, color.y, color.z, color.w]);
+        }
+        // any further and the shadow would be looked up in the neighbouring glyphs
+        let shadow_offset = self
+            .shadow_offset
+            .map(|offset| offset.clamp(-SDF_SPREAD, SDF_SPREAD));
+        custom_params.extend_from_slice(&[
+            shadow_offset.x,
+            shadow_offset.y,
+            SDF_SPREAD,
+            self.weight,
+            self.outline_width,
+            self.glow_width,
+            self.shadow_softness,
+            0.0, // padding to a multiple of 16 bytes
+        ]);
+        MaterialUniforms {
+            tint,
+            custom_params,
+        }
+    }
+}
+
+impl Material {
+    // atlas is one of AssetManager::font_atlases, the font's mode picks the shader, the sprite
+    // shader for bitmap fonts or one built from SDF_SHADER for SDF fonts
+    pub fn text(atlas: TextureKey, shader: ShaderKey) -> Self {
+        Self {
+            textures: vec![atlas],
+            shader,
+            blend_mode: BlendMode::AlphaBlend,
+            cull_mode: FaceCullMode::None,
+            // SDF glyphs need interpolating between texels to stay smooth when scaled up
+            filter_mode: FilterMode::Linear,
+            wrap_mode: (WrapMode::Clamp, WrapMode::Clamp),
+        }
+    }
+}
+
+// the pipeline for SDF_SHADER, bind group 0 is the atlas, 1 the camera and 2 an SdfParamsBuffer
+pub fn create_sdf_pipeline(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    let uniform = |visibility, min_size| wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: wgpu::BufferSize::new(min_size),
+        },
+        count: None,
+    };
+    let atlas_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("sdf_atlas_bind_group_layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    });
+    let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("sdf_camera_bind_group_layout"),
+        entries: &[uniform(wgpu::ShaderStages::VERTEX, 64)],
+    });
+    let params_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("sdf_params_bind_group_layout"),
+        entries: &[uniform(wgpu::ShaderStages::FRAGMENT, SDF_PARAMS_SIZE)],
+    });
+
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("SDF Pipeline Layout"),
+        bind_group_layouts: &[&atlas_layout, &camera_layout, &params_layout],
+        push_constant_ranges: &[],
+    });
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("sdf.wgsl"),
+        source: wgpu::ShaderSource::Wgsl(SDF_SHADER.into()),
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("SDF Pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some("vs_main"),
+            buffers: &[Vertex::desc(), ModelInstanceRaw::desc()],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        // both faces, like Material::text
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            cull_mode: None,
+            ..Default::default()
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
+// bind group 2 of an SDF pipeline, one per SdfStyle in use, rewritten when the style changes
+pub struct SdfParamsBuffer {
+    pub buffer: wgpu::Buffer,
+    pub bind_group: wgpu::BindGroup,
+}
+impl SdfParamsBuffer {
+    pub fn new(
+        device: &wgpu::Device,
+        pipeline: &wgpu::RenderPipeline,
+        uniforms: &MaterialUniforms,
+    ) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("SDF Params Buffer"),
+            contents: bytemuck::cast_slice(&sdf_params(uniforms)),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("sdf_params_bind_group"),
+            layout: &pipeline.get_bind_group_layout(2),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        Self { buffer, bind_group }
+    }
+
+    pub fn write(&self, queue: &wgpu::Queue, uniforms: &MaterialUniforms) {
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&sdf_params(uniforms)));
+    }
+}
+
+// laid out as SdfParams, missing custom_params are zero
+fn sdf_params(uniforms: &MaterialUniforms) -> [f32; SDF_PARAMS_SIZE as usize / 4] {
+    let tint = uniforms.tint;
+    let mut params = [0.0; SDF_PARAMS_SIZE as usize / 4];
+    for (param, value) in params.iter_mut().zip(
+        [tint.x, tint.y, tint.z, tint.w]
+            .iter()
+            .chain(&uniforms.custom_params),
+    ) {
+        *param = *value;
+    }
+    params
+}
+
+impl AssetManager {
+    // the SDF pipeline for render targets of format, built the first time it's asked for
+    pub fn load_sdf_shader(
+        &self,
+        render_context: &RenderContext,
+        format: wgpu::TextureFormat,
+    ) -> ShaderKey {
+        let name = format!("sdf.wgsl {:?}", format);
+        if let Some(key) = self.shader_assets.read().unwrap().get(&name) {
+            return *key;
+        }
+        let pipeline = create_sdf_pipeline(&render_context.device, format);
+        let key = self.shaders.write().unwrap().insert(pipeline);
+        self.shader_assets.write().unwrap().insert(name, key);
+        key
+    }
+
+    // font_materials with the SDF pipeline, for fonts loaded with load_sdf_font
+    pub fn sdf_materials(
+        &self,
+        render_context: &RenderContext,
+        key: FontKey,
+        format: wgpu::TextureFormat,
+    ) -> Vec<MaterialKey> {
+        let shader = self.load_sdf_shader(render_context, format);
+        self.font_materials(key, shader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgpu::naga;
+
+    #[test]
+    fn params_match_the_shader() {
+        let module = naga::front::wgsl::parse_str(SDF_SHADER).unwrap();
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::all(),
+        )
+        .validate(&module)
+        .unwrap();
+
+        let (_, params) = module
+            .global_variables
+            .iter()
+            .find(|(_, var)| {
+                var.binding
+                    == Some(naga::ResourceBinding {
+                        group: 2,
+                        binding: 0,
+                    })
+            })
+            .unwrap();
+        let size = module.types[params.ty].inner.size(module.to_ctx());
+        assert_eq!(size as u64, SDF_PARAMS_SIZE);
+
+        let style = SdfStyle::new()
+            .with_outline(1.0, Vector4::new(0.0, 0.0, 0.0, 1.0))
+            .with_shadow(
+                Vector2::new(2.0, 2.0),
+                1.0,
+                Vector4::new(0.0, 0.0, 0.0, 0.5),
+            );
+        let uniforms = style.to_uniforms(Vector4::new(1.0, 1.0, 1.0, 1.0));
+        assert_eq!(4 + uniforms.custom_params.len() as u64, SDF_PARAMS_SIZE / 4);
+        // the spread lands where SdfParams has it, after the four colours and the shadow offset
+        assert_eq!(sdf_params(&uniforms)[18], SDF_SPREAD);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::*;
+use std::{any::Any, collections::VecDeque};
+
+use cgmath::Vector2;
+
+pub struct Message {
+    pub from: u32,
+    pub to: Option<u32>, // None goes to every window but the sender
+    pub payload: Box<dyn Any>,
+}
+
+// a UI element being dragged, possibly out of its window and into another
+pub struct DragSession {
+    pub source: u32,
+    pub payload: Box<dyn Any>,
+    pub screen_position: Vector2<f32>, // desktop window coordinates
+    pub hover: Option<(u32, Vector2<f32>)>, // window under the cursor and the logical position in it
+}
+
+// state owned by SQ that every window can reach through its Context
+pub struct Shared {
+    pub screens: ScreenManager,
+    pub clipboard: Clipboard,
+    pub gamepads: GamepadManager,
+    pub drag: Option<DragSession>,
+    pub(crate) spawn_queue: Vec<WindowBuilder>,
+    pub(crate) messages: VecDeque<Message>,
+}
+impl Shared {
+    pub fn new(clipboard: Clipboard, gamepads: GamepadManager) -> Self {
+        Self {
+            screens: ScreenManager::new(),
+            clipboard,
+            gamepads,
+            drag: None,
+            spawn_queue: vec![],
+            messages: VecDeque::new(),
+        }
+    }
+
+    // opened at the start of the next update, on_start is called for it as usual
+    pub fn spawn_window(&mut self, builder: WindowBuilder) {
+        self.spawn_queue.push(builder);
+    }
+}
+
+impl Context<'_> {
+    // the child is closed along with this window
+    pub fn spawn_child(&mut self, builder: WindowBuilder) {
+        self.shared
+            .spawn_window(builder.with_parent(self.window_id));
+    }
+
+    // delivered through on_message at the end of the current (or, from on_render, next) update
+    pub fn send(&mut self, to: u32, payload: impl Any) {
+        self.shared.messages.push_back(Message {
+            from: self.window_id,
+            to: Some(to),
+            payload: Box::new(payload),
+        });
+    }
+
+    pub fn broadcast(&mut self, payload: impl Any) {
+        self.shared.messages.push_back(Message {
+            from: self.window_id,
+            to: None,
+            payload: Box::new(payload),
+        });
+    }
+
+    // call while the left mouse button is held, the drag ends when it is released
+    // returns false if another drag is already in progress
+    pub fn start_drag(&mut self, payload: impl Any) -> bool {
+        if self.shared.drag.is_some() {
+            return false;
+        }
+
+        let (x, y) = self.window.position();
+        let Vector2 { x: mx, y: my } = self
+            .inputs
+            .mouse_position()
+            .unwrap_or(Vector2::new(0.0, 0.0));
+        let scale = self.inputs.logical_scale;
+        self.shared.drag = Some(DragSession {
+            source: self.window_id,
+            payload: Box::new(payload),
+            screen_position: Vector2::new(x as f32 + mx / scale, y as f32 + my / scale),
+            hover: Some((self.window_id, Vector2::new(mx, my))),
+        });
+        true
+    }
+}
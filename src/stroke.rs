@@ -0,0 +1,186 @@
+use crate::*;
+
+use cgmath::{InnerSpace, Vector2, VectorSpace};
+use sdl3::event::Event;
+
+// the most steps the gap between two real samples is split into
+const MAX_INTERPOLATED: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PenSample {
+    pub timestamp: u64,         // nanoseconds, SDL's event clock
+    pub position: Vector2<f32>, // logical units
+    pub pressure: f32,          // 0.0 ~ 1.0
+    pub tilt: Vector2<f32>,     // -90.0 ~ 90.0, left-right, top-down
+    pub rotation: f32,          // -180.0 ~ 179.9, clockwise, barrel rotation
+    pub eraser: bool,
+    pub interpolated: bool, // filled in between two real samples
+}
+impl PenSample {
+    fn lerp(&self, other: &PenSample, t: f32) -> PenSample {
+        PenSample {
+            timestamp: self.timestamp
+                + ((other.timestamp.saturating_sub(self.timestamp)) as f64 * t as f64) as u64,
+            position: self.position.lerp(other.position, t),
+            pressure: self.pressure + (other.pressure - self.pressure) * t,
+            tilt: self.tilt.lerp(other.tilt, t),
+            rotation: self.rotation + (other.rotation - self.rotation) * t,
+            eraser: self.eraser,
+            interpolated: true,
+        }
+    }
+}
+
+// everything between one pen down and the following pen up
+#[derive(Debug, Clone, Default)]
+pub struct Stroke {
+    pub samples: Vec<PenSample>,
+    pub eraser: bool,
+}
+impl Stroke {
+    pub fn length(&self) -> f32 {
+        self.samples
+            .windows(2)
+            .map(|pair| (pair[1].position - pair[0].position).magnitude())
+            .sum()
+    }
+
+    pub fn bounds(&self) -> Option<Rectangle<f32>> {
+        let first = self.samples.first()?.position;
+        let (min, max) = self
+            .samples
+            .iter()
+            .fold((first, first), |(min, max), sample| {
+                (
+                    Vector2::new(min.x.min(sample.position.x), min.y.min(sample.position.y)),
+                    Vector2::new(max.x.max(sample.position.x), max.y.max(sample.position.y)),
+                )
+            });
+        Some(Rectangle {
+            x: min.x,
+            y: min.y,
+            width: max.x - min.x,
+            height: max.y - min.y,
+        })
+    }
+}
+
+// keeps every pen sample SDL delivers while the pen is down, PenState only has the latest one
+pub struct StrokeRecorder {
+    pub current: Option<Stroke>,
+    pub spacing: f32, // samples further apart than this (logical) get interpolated ones in between
+    frame_samples: Vec<PenSample>,
+    finished: Vec<Stroke>,
+}
+impl StrokeRecorder {
+    pub fn new() -> Self {
+        Self {
+            current: None,
+            spacing: 2.0,
+            frame_samples: vec![],
+            finished: vec![],
+        }
+    }
+
+    // samples added to the current stroke since the last tick, for drawing incrementally
+    pub fn frame_samples(&self) -> &[PenSample] {
+        &self.frame_samples
+    }
+
+    // strokes that ended since the last call
+    pub fn take_finished(&mut self) -> Vec<Stroke> {
+        std::mem::take(&mut self.finished)
+    }
+
+    // called after InputManager has applied the event to pen
+    pub fn handle_event(&mut self, event: &Event, pen: &PenState, logical_scale: f32) {
+        let (timestamp, x, y) = match event {
+            Event::PenDown {
+                timestamp, x, y, ..
+            }
+            | Event::PenUp {
+                timestamp, x, y, ..
+            }
+            | Event::PenMotion {
+                timestamp, x, y, ..
+            }
+            | Event::PenAxis {
+                timestamp, x, y, ..
+            } => (*timestamp, *x, *y),
+            _ => return,
+        };
+        let sample = PenSample {
+            timestamp,
+            position: Vector2::new(x, y) * logical_scale,
+            pressure: pen.pressure,
+            tilt: pen.tilt,
+            rotation: pen.rotation,
+            eraser: pen.eraser,
+            interpolated: false,
+        };
+
+        match event {
+            Event::PenDown { eraser, .. } => {
+                self.current = Some(Stroke {
+                    samples: vec![],
+                    eraser: *eraser,
+                });
+                self.push(sample);
+            }
+            Event::PenUp { .. } => {
+                let Some(eraser) = self.current.as_ref().map(|stroke| stroke.eraser) else {
+                    return;
+                };
+                // the pen state is already reset by now, lift-off has no pressure either way
+                self.push(PenSample { eraser, ..sample });
+                self.finished.extend(self.current.take());
+            }
+            _ => self.push(sample),
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.frame_samples.clear();
+    }
+
+    pub fn reset(&mut self) {
+        self.finished.extend(self.current.take());
+        self.frame_samples.clear();
+    }
+
+    fn push(&mut self, sample: PenSample) {
+        let Some(stroke) = &mut self.current else {
+            return;
+        };
+
+        if let Some(last) = stroke.samples.last_mut() {
+            // SDL sends each axis of one hardware report as its own event, merge them back
+            if last.timestamp == sample.timestamp {
+                *last = sample;
+                match self.frame_samples.last_mut() {
+                    Some(frame_last) if frame_last.timestamp == sample.timestamp => {
+                        *frame_last = sample
+                    }
+                    _ => self.frame_samples.push(sample),
+                }
+                return;
+            }
+
+            // SDL coalesces motion while the app is busy, fill in the gap
+            let last = *last;
+            let distance = (sample.position - last.position).magnitude();
+            if self.spacing > 0.0 && distance > self.spacing {
+                // a tiny spacing or a jump across the screen mustn't flood the stroke
+                let steps = ((distance / self.spacing).ceil() as usize).min(MAX_INTERPOLATED);
+                for i in 1..steps {
+                    let between = last.lerp(&sample, i as f32 / steps as f32);
+                    stroke.samples.push(between);
+                    self.frame_samples.push(between);
+                }
+            }
+        }
+
+        stroke.samples.push(sample);
+        self.frame_samples.push(sample);
+    }
+}
@@ -24,6 +24,13 @@ impl<S: BaseNum> Rectangle<S> {
             y: self.y + self.height,
         }
     }
+
+    pub fn contains(&self, point: Vector2<S>) -> bool {
+        point.x >= self.x
+            && point.y >= self.y
+            && point.x < self.x + self.width
+            && point.y < self.y + self.height
+    }
 }
 
 #[repr(C)]
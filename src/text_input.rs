@@ -0,0 +1,167 @@
+use crate::*;
+
+use anyhow::anyhow;
+use sdl3::{event::Event, sys::rect::SDL_Rect, video::Window};
+
+// text an IME is still working on, e.g. kana waiting to be converted to kanji, it is replaced
+// by committed text or dropped once the user confirms or cancels
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Composition {
+    pub text: String,
+    pub cursor: usize,    // byte offset into text
+    pub selection: usize, // bytes after the cursor the IME is converting, 0 for none
+}
+impl Composition {
+    pub fn selected(&self) -> &str {
+        &self.text[self.cursor..self.cursor + self.selection]
+    }
+}
+
+// SDL only sends text while text input is started, and starting it is what brings up the IME
+// and on-screen keyboards, so it follows whichever text field has focus
+pub struct TextInputManager {
+    pub composition: Option<Composition>,
+    // the app draws the composition itself, the IME only shows its candidate list
+    pub inline_composition: bool,
+    focused: Option<u64>,
+    committed: String,
+}
+impl TextInputManager {
+    pub fn new() -> Self {
+        Self {
+            composition: None,
+            inline_composition: false,
+            focused: None,
+            committed: String::new(),
+        }
+    }
+
+    // field is any id the app uses for the focused text field
+    pub fn focus(&mut self, window: &Window, field: u64) -> anyhow::Result<()> {
+        if self.focused == Some(field) {
+            return Ok(());
+        }
+        // SDL reads the hint when text input starts
+        let implemented_ui = if self.inline_composition {
+            "composition"
+        } else {
+            "none"
+        };
+        sdl3::hint::set("SDL_IME_IMPLEMENTED_UI", implemented_ui);
+
+        // Safety: the window is alive for the duration of the calls
+        unsafe {
+            // the previous field's composition must not end up in the new one
+            sdl3::sys::keyboard::SDL_ClearComposition(window.raw());
+            if !sdl3::sys::keyboard::SDL_StartTextInput(window.raw()) {
+                return Err(anyhow!("Failed to start text input: {}", sdl3::get_error()));
+            }
+        }
+        self.focused = Some(field);
+        self.composition = None;
+        Ok(())
+    }
+
+    pub fn unfocus(&mut self, window: &Window) {
+        if self.focused.take().is_none() {
+            return;
+        }
+        // Safety: the window is alive for the duration of the call
+        unsafe {
+            sdl3::sys::keyboard::SDL_StopTextInput(window.raw());
+        }
+        self.composition = None;
+    }
+
+    pub fn focused(&self) -> Option<u64> {
+        self.focused
+    }
+
+    pub fn is_focused(&self, field: u64) -> bool {
+        self.focused == Some(field)
+    }
+
+    // key presses belong to the IME while this is true, e.g. Enter confirms a conversion
+    pub fn is_composing(&self) -> bool {
+        self.composition.is_some()
+    }
+
+    // the text field's bounds and the cursor's x offset inside them, both logical,
+    // the IME places its candidate list next to it
+    pub fn set_area(
+        &self,
+        window: &Window,
+        bounds: Rectangle<f32>,
+        cursor: f32,
+        logical_scale: f32,
+    ) -> anyhow::Result<()> {
+        let rect = SDL_Rect {
+            x: (bounds.x / logical_scale).round() as i32,
+            y: (bounds.y / logical_scale).round() as i32,
+            w: (bounds.width / logical_scale).round() as i32,
+            h: (bounds.height / logical_scale).round() as i32,
+        };
+        let cursor = (cursor / logical_scale).round() as i32;
+        // Safety: the window is alive and SDL copies the rectangle
+        if unsafe { sdl3::sys::keyboard::SDL_SetTextInputArea(window.raw(), &rect, cursor) } {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Failed to set text input area: {}",
+                sdl3::get_error()
+            ))
+        }
+    }
+
+    // text typed or confirmed since the last call, to be inserted at the field's cursor
+    pub fn take_committed(&mut self) -> String {
+        std::mem::take(&mut self.committed)
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        match event {
+            Event::TextInput { text, .. } => {
+                self.committed.push_str(text);
+                self.composition = None;
+            }
+            Event::TextEditing {
+                text,
+                start,
+                length,
+                ..
+            } => {
+                if text.is_empty() {
+                    self.composition = None;
+                    return;
+                }
+                // SDL counts characters, -1 when the IME doesn't say
+                let byte_offset = |chars: usize| {
+                    text.char_indices()
+                        .nth(chars)
+                        .map_or(text.len(), |(i, _)| i)
+                };
+                let cursor = if *start < 0 {
+                    text.len()
+                } else {
+                    byte_offset(*start as usize)
+                };
+                let selection = if *start < 0 || *length <= 0 {
+                    0
+                } else {
+                    byte_offset((*start + *length) as usize) - cursor
+                };
+                self.composition = Some(Composition {
+                    text: text.clone(),
+                    cursor,
+                    selection,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    // the IME drops the composition when the window loses focus, text input itself stays on
+    pub fn reset(&mut self) {
+        self.composition = None;
+    }
+}
@@ -1,7 +1,7 @@
 use crate::*;
 use std::sync::Arc;
 
-use image::GenericImageView;
+use image::{GenericImageView, RgbaImage};
 use sdl3::*;
 use wgpu::*;
 
@@ -23,7 +23,9 @@ impl SQTexture {
             sample_count: 1,
             dimension: TextureDimension::D2,
             format: TextureFormat::Rgba8UnormSrgb,
-            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::RENDER_ATTACHMENT
+                | TextureUsages::COPY_SRC,
             view_formats: &[],
         };
 
@@ -77,7 +79,9 @@ impl SQTexture {
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
             view_formats: &[],
             size,
         });
@@ -124,4 +128,62 @@ impl SQTexture {
     pub fn format(&self) -> TextureFormat {
         self.texture.format()
     }
+
+    // copies the texture back from the GPU, blocks until it arrives
+    pub fn to_image(&self, render_context: &RenderContext) -> anyhow::Result<RgbaImage> {
+        let bgra = match self.format() {
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+            format => return Err(anyhow::anyhow!("Can't read back {:?} textures", format)),
+        };
+        let device = &render_context.device;
+        let (width, height) = self.size();
+        // rows of a texture to buffer copy are padded to 256 bytes
+        let row = width * 4;
+        let padded_row = row.div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT) * COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Texture Readback"),
+            size: (padded_row * height) as u64,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Texture Readback"),
+        });
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            self.texture.size(),
+        );
+        render_context.queue.submit([encoder.finish()]);
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        device.poll(Maintain::Wait);
+        receiver.recv()??;
+
+        let mut pixels = Vec::with_capacity((row * height) as usize);
+        for padded in slice.get_mapped_range().chunks(padded_row as usize) {
+            pixels.extend_from_slice(&padded[..row as usize]);
+        }
+        buffer.unmap();
+        if bgra {
+            for pixel in pixels.chunks_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+        RgbaImage::from_raw(width, height, pixels)
+            .ok_or(anyhow::anyhow!("Texture readback has the wrong size"))
+    }
 }
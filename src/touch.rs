@@ -0,0 +1,365 @@
+use crate::*;
+use std::time::Duration;
+
+use cgmath::{InnerSpace, Vector2};
+use sdl3::event::Event;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GesturePhase {
+    Began,
+    Changed,
+    Ended,
+}
+
+// positions and distances are in logical units
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gesture {
+    Tap {
+        position: Vector2<f32>,
+    },
+    DoubleTap {
+        position: Vector2<f32>, // follows the Tap of the second touch
+    },
+    LongPress {
+        position: Vector2<f32>,
+    },
+    Pan {
+        phase: GesturePhase,
+        position: Vector2<f32>, // centroid of all fingers down
+        delta: Vector2<f32>,
+        fingers: usize,
+    },
+    Pinch {
+        phase: GesturePhase,
+        center: Vector2<f32>,
+        scale: f32, // relative to the finger distance when the pinch began
+    },
+    Rotate {
+        phase: GesturePhase,
+        center: Vector2<f32>,
+        angle: f32, // radians, clockwise, relative to when the rotation began
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TouchPoint {
+    pub id: u64,
+    pub position: Vector2<f32>,
+    pub start_position: Vector2<f32>,
+    pub pressure: f32, // 0.0 ~ 1.0
+    pub started: u64,  // SDL event timestamp, nanoseconds
+}
+
+// the two fingers a pinch or rotation is measured between
+struct TwoFingerStart {
+    distance: f32,
+    angle: f32,
+    pinching: bool,
+    rotating: bool,
+    scale: f32, // the last reported values, repeated when the gesture ends
+    rotation: f32,
+}
+
+pub struct TouchManager {
+    pub points: Vec<TouchPoint>, // in the order the fingers went down
+    pub gestures: Vec<Gesture>,  // emitted since the last take_gestures
+
+    pub slop: f32, // how far a finger may wander before a tap turns into a pan
+    pub tap_duration: Duration,
+    pub double_tap_interval: Duration,
+    pub long_press_duration: Duration,
+    pub rotate_threshold: f32, // radians
+
+    tap_candidate: bool,
+    last_tap: Option<(u64, Vector2<f32>)>,
+    panning: bool,
+    last_centroid: Option<Vector2<f32>>,
+    two_finger: Option<TwoFingerStart>,
+}
+impl TouchManager {
+    pub fn new() -> Self {
+        Self {
+            points: vec![],
+            gestures: vec![],
+            slop: 10.0,
+            tap_duration: Duration::from_millis(300),
+            double_tap_interval: Duration::from_millis(300),
+            long_press_duration: Duration::from_millis(500),
+            rotate_threshold: 0.1,
+            tap_candidate: false,
+            last_tap: None,
+            panning: false,
+            last_centroid: None,
+            two_finger: None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.end_gestures();
+        self.points.clear();
+        self.tap_candidate = false;
+        self.last_centroid = None;
+    }
+
+    pub fn take_gestures(&mut self) -> Vec<Gesture> {
+        std::mem::take(&mut self.gestures)
+    }
+
+    pub fn centroid(&self) -> Option<Vector2<f32>> {
+        if self.points.is_empty() {
+            return None;
+        }
+        let sum = self
+            .points
+            .iter()
+            .fold(Vector2::new(0.0, 0.0), |sum, point| sum + point.position);
+        Some(sum / self.points.len() as f32)
+    }
+
+    // SDL reports fingers normalized to the window, window_size is the logical size that converts
+    // them to logical units
+    pub fn handle_event(&mut self, event: &Event, window_size: Vector2<f32>) {
+        let now = event.get_timestamp();
+        match event {
+            Event::FingerDown {
+                finger_id,
+                x,
+                y,
+                pressure,
+                ..
+            } => {
+                let position = Vector2::new(x * window_size.x, y * window_size.y);
+                self.points.push(TouchPoint {
+                    id: *finger_id,
+                    position,
+                    start_position: position,
+                    pressure: *pressure,
+                    started: now,
+                });
+
+                self.tap_candidate = self.points.len() == 1;
+                if self.points.len() == 2 {
+                    self.start_two_finger();
+                }
+                // the centroid jumps when a finger is added
+                self.last_centroid = self.centroid();
+            }
+            Event::FingerMotion {
+                finger_id,
+                x,
+                y,
+                pressure,
+                ..
+            } => {
+                let Some(point) = self.points.iter_mut().find(|p| p.id == *finger_id) else {
+                    return;
+                };
+                point.position = Vector2::new(x * window_size.x, y * window_size.y);
+                point.pressure = *pressure;
+                let moved = (point.position - point.start_position).magnitude() > self.slop;
+
+                if moved {
+                    self.tap_candidate = false;
+                }
+                self.update_pan(moved);
+                self.update_two_finger();
+            }
+            Event::FingerUp {
+                finger_id, x, y, ..
+            } => {
+                let Some(index) = self.points.iter().position(|p| p.id == *finger_id) else {
+                    return;
+                };
+                let point = self.points.remove(index);
+                let position = Vector2::new(x * window_size.x, y * window_size.y);
+
+                if self.tap_candidate && since(point.started, now) <= self.tap_duration {
+                    self.gestures.push(Gesture::Tap { position });
+                    let double = self.last_tap.is_some_and(|(time, last)| {
+                        since(time, now) <= self.double_tap_interval
+                            && (position - last).magnitude() <= self.slop * 2.0
+                    });
+                    if double {
+                        self.gestures.push(Gesture::DoubleTap { position });
+                        self.last_tap = None;
+                    } else {
+                        self.last_tap = Some((now, position));
+                    }
+                }
+                self.tap_candidate = false;
+
+                if self.points.len() < 2 {
+                    self.end_two_finger(position);
+                } else if index < 2 {
+                    // one of the two fingers being measured lifted, carry on with the next two
+                    self.end_two_finger(position);
+                    self.start_two_finger();
+                }
+                if self.points.is_empty() && self.panning {
+                    self.panning = false;
+                    self.gestures.push(Gesture::Pan {
+                        phase: GesturePhase::Ended,
+                        position,
+                        delta: Vector2::new(0.0, 0.0),
+                        fingers: 0,
+                    });
+                }
+                self.last_centroid = self.centroid();
+            }
+            _ => {}
+        }
+    }
+
+    // long presses fire while the finger is still down, so they are checked every update,
+    // now is on SDL's event clock like the event timestamps
+    pub fn update(&mut self, now: u64) {
+        let Some(point) = self.points.first() else {
+            return;
+        };
+        if self.tap_candidate && since(point.started, now) >= self.long_press_duration {
+            self.tap_candidate = false;
+            self.gestures.push(Gesture::LongPress {
+                position: point.position,
+            });
+        }
+    }
+
+    fn update_pan(&mut self, moved: bool) {
+        let Some(centroid) = self.centroid() else {
+            return;
+        };
+        let delta = centroid - self.last_centroid.unwrap_or(centroid);
+        self.last_centroid = Some(centroid);
+
+        if !self.panning {
+            if !moved {
+                return;
+            }
+            self.panning = true;
+            self.gestures.push(Gesture::Pan {
+                phase: GesturePhase::Began,
+                position: centroid,
+                delta: Vector2::new(0.0, 0.0),
+                fingers: self.points.len(),
+            });
+        }
+        self.gestures.push(Gesture::Pan {
+            phase: GesturePhase::Changed,
+            position: centroid,
+            delta,
+            fingers: self.points.len(),
+        });
+    }
+
+    fn update_two_finger(&mut self) {
+        let (Some((center, distance, angle)), Some(start)) =
+            (self.two_finger_metrics(), self.two_finger.as_mut())
+        else {
+            return;
+        };
+
+        if !start.pinching && (distance - start.distance).abs() > self.slop {
+            start.pinching = true;
+            self.gestures.push(Gesture::Pinch {
+                phase: GesturePhase::Began,
+                center,
+                scale: 1.0,
+            });
+        }
+        let rotation = wrap_angle(angle - start.angle);
+        if !start.rotating && rotation.abs() > self.rotate_threshold {
+            start.rotating = true;
+            self.gestures.push(Gesture::Rotate {
+                phase: GesturePhase::Began,
+                center,
+                angle: 0.0,
+            });
+        }
+
+        if start.pinching {
+            start.scale = distance / start.distance.max(f32::EPSILON);
+            self.gestures.push(Gesture::Pinch {
+                phase: GesturePhase::Changed,
+                center,
+                scale: start.scale,
+            });
+        }
+        if start.rotating {
+            start.rotation = rotation;
+            self.gestures.push(Gesture::Rotate {
+                phase: GesturePhase::Changed,
+                center,
+                angle: rotation,
+            });
+        }
+    }
+
+    fn start_two_finger(&mut self) {
+        self.two_finger = self
+            .two_finger_metrics()
+            .map(|(_, distance, angle)| TwoFingerStart {
+                distance,
+                angle,
+                pinching: false,
+                rotating: false,
+                scale: 1.0,
+                rotation: 0.0,
+            });
+    }
+
+    fn end_two_finger(&mut self, center: Vector2<f32>) {
+        let Some(start) = self.two_finger.take() else {
+            return;
+        };
+        if start.pinching {
+            self.gestures.push(Gesture::Pinch {
+                phase: GesturePhase::Ended,
+                center,
+                scale: start.scale,
+            });
+        }
+        if start.rotating {
+            self.gestures.push(Gesture::Rotate {
+                phase: GesturePhase::Ended,
+                center,
+                angle: start.rotation,
+            });
+        }
+    }
+
+    fn end_gestures(&mut self) {
+        let center = self.centroid().unwrap_or(Vector2::new(0.0, 0.0));
+        self.end_two_finger(center);
+        if self.panning {
+            self.panning = false;
+            self.gestures.push(Gesture::Pan {
+                phase: GesturePhase::Ended,
+                position: center,
+                delta: Vector2::new(0.0, 0.0),
+                fingers: 0,
+            });
+        }
+    }
+
+    // center, distance and angle between the first two fingers
+    fn two_finger_metrics(&self) -> Option<(Vector2<f32>, f32, f32)> {
+        let [a, b, ..] = self.points.as_slice() else {
+            return None;
+        };
+        let between = b.position - a.position;
+        Some((
+            (a.position + b.position) / 2.0,
+            between.magnitude(),
+            between.y.atan2(between.x),
+        ))
+    }
+}
+
+fn since(then: u64, now: u64) -> Duration {
+    Duration::from_nanos(now.saturating_sub(then))
+}
+
+fn wrap_angle(angle: f32) -> f32 {
+    use std::f32::consts::PI;
+    (angle + PI).rem_euclid(2.0 * PI) - PI
+}
@@ -0,0 +1,211 @@
+use crate::*;
+
+use image::RgbaImage;
+use sdl3::{pixels::PixelFormat, surface::Surface, video::Window};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowPosition {
+    Centered,
+    At(i32, i32), // window coordinates
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fullscreen {
+    // borderless, covering the display at its desktop resolution
+    Desktop,
+    // switches the display mode, the closest refresh rate available at that resolution is used
+    Exclusive {
+        width: u32,
+        height: u32,
+        refresh_rate: f32,
+    },
+}
+
+// sizes are logical, defaults match the borderless look of SQ::create_window
+pub struct WindowBuilder {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+    pub decorations: bool,
+    pub resizable: bool,
+    pub position: WindowPosition,
+    pub min_size: Option<(u32, u32)>,
+    pub max_size: Option<(u32, u32)>,
+    pub fullscreen: Option<Fullscreen>,
+    pub always_on_top: bool,
+    pub transparent: bool, // composited with premultiplied alpha, see SQWindow::clear_color
+    pub icon: Option<RgbaImage>,
+    pub hidden: bool,
+    pub parent: Option<u32>, // stays above its parent and is closed with it
+    pub utility: bool,       // tool window, kept out of the taskbar
+}
+impl WindowBuilder {
+    pub fn new(title: impl Into<String>, width: u32, height: u32) -> Self {
+        Self {
+            title: title.into(),
+            width,
+            height,
+            decorations: false,
+            resizable: true,
+            position: WindowPosition::Centered,
+            min_size: None,
+            max_size: None,
+            fullscreen: None,
+            always_on_top: false,
+            transparent: false,
+            icon: None,
+            hidden: false,
+            parent: None,
+            utility: false,
+        }
+    }
+
+    pub fn with_decorations(mut self, decorations: bool) -> Self {
+        self.decorations = decorations;
+        self
+    }
+
+    pub fn with_resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    pub fn with_position(mut self, position: WindowPosition) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn with_min_size(mut self, width: u32, height: u32) -> Self {
+        self.min_size = Some((width, height));
+        self
+    }
+
+    pub fn with_max_size(mut self, width: u32, height: u32) -> Self {
+        self.max_size = Some((width, height));
+        self
+    }
+
+    pub fn with_fullscreen(mut self, fullscreen: Fullscreen) -> Self {
+        self.fullscreen = Some(fullscreen);
+        self
+    }
+
+    pub fn with_always_on_top(mut self, always_on_top: bool) -> Self {
+        self.always_on_top = always_on_top;
+        self
+    }
+
+    pub fn with_transparency(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    pub fn with_icon(mut self, icon: RgbaImage) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
+    pub fn with_hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    pub fn with_parent(mut self, window_id: u32) -> Self {
+        self.parent = Some(window_id);
+        self
+    }
+
+    pub fn with_utility(mut self, utility: bool) -> Self {
+        self.utility = utility;
+        self
+    }
+
+    // builds the SDL window, everything that needs the window to exist is applied in SQWindow::new
+    pub(crate) fn build_sdl(&self, render_context: &RenderContext) -> anyhow::Result<Window> {
+        let video = &render_context.video_subsystem;
+        let mut builder = video.window(&self.title, self.width, self.height);
+        builder.high_pixel_density().vulkan();
+
+        match self.position {
+            WindowPosition::Centered => builder.position_centered(),
+            WindowPosition::At(x, y) => builder.position(x, y),
+        };
+        if !self.decorations {
+            builder.borderless();
+        }
+        if self.resizable {
+            builder.resizable();
+        }
+        if self.hidden {
+            builder.hidden();
+        }
+        if self.fullscreen.is_some() {
+            builder.fullscreen();
+        }
+
+        let mut flags = builder.window_flags();
+        if self.always_on_top {
+            flags |= sdl3::sys::video::SDL_WINDOW_ALWAYS_ON_TOP as u32;
+        }
+        if self.transparent {
+            flags |= sdl3::sys::video::SDL_WINDOW_TRANSPARENT as u32;
+        }
+        if self.utility {
+            flags |= sdl3::sys::video::SDL_WINDOW_UTILITY as u32;
+        }
+        builder.set_window_flags(flags);
+
+        let mut window = builder.build()?;
+
+        if let Some(icon) = &self.icon {
+            let mut pixels = icon.as_raw().clone();
+            let surface = Surface::from_data(
+                &mut pixels,
+                icon.width(),
+                icon.height(),
+                icon.width() * 4,
+                PixelFormat::try_from(sdl3::sys::pixels::SDL_PIXELFORMAT_RGBA32)?,
+            )?;
+            window.set_icon(surface);
+        }
+
+        if let Some(Fullscreen::Exclusive {
+            width,
+            height,
+            refresh_rate,
+        }) = self.fullscreen
+        {
+            let mode = window
+                .get_display()?
+                .get_fullscreen_modes()?
+                .into_iter()
+                .filter(|mode| mode.w == width as i32 && mode.h == height as i32)
+                .min_by(|a, b| {
+                    (a.refresh_rate - refresh_rate)
+                        .abs()
+                        .total_cmp(&(b.refresh_rate - refresh_rate).abs())
+                })
+                .ok_or(anyhow::anyhow!(
+                    "No fullscreen mode available at {}x{}",
+                    width,
+                    height
+                ))?;
+            window.set_display_mode(mode)?;
+        }
+
+        Ok(window)
+    }
+}
+
+pub(crate) fn set_parent(window: &Window, parent: &Window) -> anyhow::Result<()> {
+    // Safety: both windows are alive for the duration of the call, SDL clears the link itself
+    // when either is destroyed
+    if unsafe { sdl3::sys::video::SDL_SetWindowParent(window.raw(), parent.raw()) } {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "Failed to set parent window: {}",
+            sdl3::get_error()
+        ))
+    }
+}